@@ -0,0 +1,111 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provide a Rust macro for converting ARM CMSIS SVD description into Rust for accessing the
+//! specified hardware.
+
+extern crate proc_macro;
+#[macro_use]
+extern crate quote;
+extern crate svd_mmap;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use svd_mmap::svd::Device;
+use svd_mmap::{gen_device, Options};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, LitStr, Token};
+
+/// The SVD filename followed by the names of any options to enable.
+struct Input {
+    filename: LitStr,
+    options: Options,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Input> {
+        let filename = input.parse()?;
+        let mut options = Options::default();
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            for option in Punctuated::<Ident, Token![,]>::parse_terminated(input)? {
+                match option.to_string().as_str() {
+                    "vector_table" => options.vector_table = true,
+                    "const_addresses" => options.const_addresses = true,
+                    _ => return Err(syn::Error::new(option.span(), "unknown svd_mmap option")),
+                }
+            }
+        }
+        Ok(Input { filename, options })
+    }
+}
+
+/// Generate the memory map for the device described by the given SVD file.
+///
+/// A relative filename is resolved against the directory of the source file invoking the macro.
+/// It may be followed by `vector_table` to generate the Cortex-M vector table of the device's
+/// interrupts and by `const_addresses` to reach each peripheral at its constant base address
+/// instead of through a linker symbol.
+///
+/// The macro only generates Rust, so the linker script must come from elsewhere. Without
+/// `const_addresses`, the peripherals link against symbols that the `.ld` written by
+/// `svd_mmap::Generator` or the `link` command line subcommand assigns. With `vector_table`,
+/// the table refers to a handler for every interrupt. Only that `.ld`, written with
+/// `vector_table(true)` or `--vector-table`, aliases the undefined handlers to `DefaultHandler`.
+/// When the `.ld` isn't passed to the linker, linking fails with undefined symbols.
+#[proc_macro]
+pub fn svd_mmap(input: TokenStream) -> TokenStream {
+    let Input { filename, options } = syn::parse_macro_input!(input as Input);
+    let path = resolve_path(&filename.value());
+
+    let mut s = String::new();
+    if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut s)) {
+        let msg = format!("unable to read {}: {}", path.display(), e);
+        return syn::Error::new(filename.span(), msg).to_compile_error().into();
+    }
+
+    // Generate SVD device data from SVD XML.
+    let dev = Device::parse(&s);
+    let items = gen_device(&dev, &options);
+
+    // Including the SVD file is what tells cargo to rebuild when it changes.
+    let path = path.to_string_lossy().into_owned();
+    let output = quote! {
+        const _: &[u8] = include_bytes!(#path);
+
+        #(#items)*
+    };
+    output.into()
+}
+
+/// Find the absolute path of the SVD file named in the macro invocation.
+fn resolve_path(filename: &str) -> PathBuf {
+    let path = Path::new(filename);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let mut cu = proc_macro::Span::call_site().local_file()
+        .or_else(|| env::var_os("CARGO_MANIFEST_DIR").map(|x| PathBuf::from(x).join("lib.rs")))
+        .unwrap_or_default();
+    cu.pop();
+    cu.push(path);
+
+    if cu.is_relative() {
+        if let Ok(dir) = env::current_dir() {
+            return dir.join(cu);
+        }
+    }
+    cu
+}
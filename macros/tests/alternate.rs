@@ -0,0 +1,55 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers sharing an address offset are reached as alternate views of the same memory.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/ALTERNATE.svd");
+
+use alternate::tim::Tim;
+
+#[test]
+fn test_alternate_layout() {
+    assert_eq!(mem::offset_of!(Tim, ccmr1_output), 0x18);
+    assert_eq!(mem::offset_of!(Tim, dr8), 0x1c);
+
+    // The struct is padded out to hold the word wide view of the byte register.
+    assert_eq!(mem::size_of::<Tim>(), 0x20);
+}
+
+#[test]
+fn test_alternate_register() {
+    let mut words = [0u32; 8];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.ccmr1_output.set_oc1m(0x6);
+    assert_eq!(periph.ccmr1_input().ic1f(), 0x6);
+
+    periph.ccmr1_input().set_ic1f(0xb);
+    assert_eq!(periph.ccmr1_output.oc1m(), 0x3);
+    assert_eq!(words[6], 0xb0);
+}
+
+#[test]
+fn test_alternate_wider_register() {
+    let mut words = [0u32; 8];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.dr().set_data(0x1234_5678);
+    assert_eq!(periph.dr8.data(), 0x78);
+    assert_eq!(words[7], 0x1234_5678);
+}
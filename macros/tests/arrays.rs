@@ -0,0 +1,71 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers repeated with `dim` are laid out as arrays or as individually named registers.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/ARRAYS.svd");
+
+use arrays::dma::Dma;
+use arrays::gpio::{Afr, Gpio};
+
+#[test]
+fn test_array_layout() {
+    assert_eq!(mem::offset_of!(Gpio, afr), 0x20);
+    assert_eq!(mem::offset_of!(Gpio, outa), 0x28);
+    assert_eq!(mem::offset_of!(Gpio, outb), 0x2c);
+    assert_eq!(mem::size_of::<Gpio>(), 0x30);
+}
+
+#[test]
+fn test_array_register() {
+    let mut words = [0u32; 12];
+    let periph: &Gpio = unsafe { &*(words.as_mut_ptr() as *const Gpio) };
+
+    periph.afr[1].set_afsel0(0x7);
+    let afr: &[Afr; 2] = &periph.afr;
+    assert_eq!(afr[1].afsel0(), 0x7);
+    assert_eq!(afr[0].afsel0(), 0);
+    assert_eq!(words[9], 0x7);
+
+    periph.outb.set_val(0xbeef);
+    assert_eq!(words[11], 0xbeef);
+}
+
+#[test]
+fn test_non_uniform_layout() {
+    // Elements spaced further apart than their size are named individually.
+    assert_eq!(mem::offset_of!(Dma, ccr1), 0x8);
+    assert_eq!(mem::offset_of!(Dma, ccr2), 0x1c);
+    assert_eq!(mem::offset_of!(Dma, ccr3), 0x30);
+    assert_eq!(mem::offset_of!(Dma, buf0), 0x50);
+    assert_eq!(mem::offset_of!(Dma, buf1), 0x58);
+    assert_eq!(mem::size_of::<Dma>(), 0x5c);
+}
+
+#[test]
+fn test_non_uniform_register() {
+    let mut words = [0u32; 0x17];
+    let periph: &Dma = unsafe { &*(words.as_mut_ptr() as *const Dma) };
+
+    periph.ccr2.set_pl(0x3);
+    assert_eq!(periph.ccr2.pl(), 0x3);
+    assert_eq!(words[7], 0x3000);
+
+    periph.buf1.set_data(0x1234_5678);
+    assert_eq!(words[0x16], 0x1234_5678);
+}
@@ -0,0 +1,70 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Clusters are laid out as nested register blocks.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/CLUSTERS.svd");
+
+use clusters::dma::{Ch, Dma, Mb};
+use clusters::dma::mb::Data;
+
+#[test]
+fn test_cluster_layout() {
+    assert_eq!(mem::offset_of!(Dma, ch), 0x8);
+    assert_eq!(mem::size_of::<Ch>(), 0x14);
+    assert_eq!(mem::offset_of!(Ch, cpar), 0x8);
+
+    assert_eq!(mem::offset_of!(Dma, mb0), 0x30);
+    assert_eq!(mem::offset_of!(Dma, mb1), 0x40);
+    assert_eq!(mem::size_of::<Mb>(), 0x10);
+    assert_eq!(mem::offset_of!(Mb, data), 0x8);
+    assert_eq!(mem::size_of::<Data>(), 0x8);
+
+    assert_eq!(mem::offset_of!(Dma, ccr), 0x50);
+    assert_eq!(mem::size_of::<Dma>(), 0x54);
+}
+
+#[test]
+fn test_cluster_array() {
+    let mut words = [0u32; 0x15];
+    let periph: &Dma = unsafe { &*(words.as_mut_ptr() as *const Dma) };
+
+    let ch: &[Ch; 2] = &periph.ch;
+    ch[1].ccr.set_pl(0x2);
+    ch[1].cndtr.set_ndt(0x100);
+    assert_eq!(ch[1].ccr.pl(), 0x2);
+    assert_eq!(words[7], 0x2000);
+    assert_eq!(words[8], 0x100);
+
+    // The channel register is distinct from the peripheral's register of the same name.
+    periph.ccr.set_all(0xffff_fffe);
+    assert_eq!(ch[0].ccr.pl(), 0);
+    assert_eq!(words[0x14], 0xffff_fffe);
+}
+
+#[test]
+fn test_nested_cluster() {
+    let mut words = [0u32; 0x15];
+    let periph: &Dma = unsafe { &*(words.as_mut_ptr() as *const Dma) };
+
+    periph.mb1.tdtr.set_dlc(0x8);
+    periph.mb1.data.hi.set_data(0xdead_beef);
+    assert_eq!(periph.mb1.data.hi.data(), 0xdead_beef);
+    assert_eq!(words[0x11], 0x8);
+    assert_eq!(words[0x13], 0xdead_beef);
+}
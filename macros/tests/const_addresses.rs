@@ -0,0 +1,41 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Peripherals are reached at their constant base addresses without a linker script.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::ops::Deref;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/DERIVED.svd", const_addresses);
+
+use derived::{tim, uart4, usart};
+
+#[test]
+fn test_base_addresses() {
+    assert_eq!(usart::USART1::PTR as usize, 0x4001_3800);
+    assert_eq!(usart::USART2::PTR as usize, 0x4000_4400);
+    assert_eq!(usart::USART3::PTR as usize, 0x4000_4800);
+    assert_eq!(uart4::UART4::PTR as usize, 0x4000_4c00);
+    assert_eq!(tim::TIM::PTR as usize, 0x4000_0000);
+}
+
+#[test]
+fn test_accessors() {
+    // The registers are only reached on the device itself, so only their types are checked.
+    let _: fn() -> &'static usart::Usart = usart::USART2::regs;
+    let _: fn(&usart::USART2) -> &usart::Usart = usart::USART2::deref;
+    let _: fn(&uart4::UART4) -> &uart4::Usart = uart4::UART4::deref;
+}
@@ -0,0 +1,71 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fields are only written with the values their write constraints allow.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/CONSTRAINTS.svd");
+
+use constraints::adc::{Adc, Mode};
+
+#[test]
+fn test_range() {
+    let mut words = [0u32; 1];
+    let periph: &Adc = unsafe { &*(words.as_mut_ptr() as *const Adc) };
+
+    assert!(periph.cfgr.set_smp(6).is_err());
+    assert!(periph.cfgr.set_smp(0).is_err());
+    assert_eq!(words[0], 0);
+
+    periph.cfgr.set_smp(5).unwrap().set_res(2);
+    assert_eq!(words[0], 0x20005);
+
+    unsafe { periph.cfgr.set_smp_unchecked(7) };
+    assert_eq!(periph.cfgr.smp(), 7);
+}
+
+#[test]
+fn test_range_beyond_field() {
+    let mut words = [0u32; 1];
+    let periph: &Adc = unsafe { &*(words.as_mut_ptr() as *const Adc) };
+
+    // The maximum is limited to the values the field can hold.
+    assert!(periph.cfgr.set_dly(16).is_err());
+    assert!(periph.cfgr.set_dly(1).is_err());
+    periph.cfgr.set_dly(15).unwrap();
+    assert_eq!(words[0], 0xf0_0000);
+}
+
+#[test]
+fn test_use_enumerated_values() {
+    let mut words = [0u32; 1];
+    let periph: &Adc = unsafe { &*(words.as_mut_ptr() as *const Adc) };
+
+    periph.cfgr.set_mode(Mode::Continuous);
+    assert!(periph.cfgr.mode() == Some(Mode::Continuous));
+    assert_eq!(words[0], 0x10);
+}
+
+#[test]
+fn test_write_as_read() {
+    let mut words = [0xa500u32; 1];
+    let periph: &Adc = unsafe { &*(words.as_mut_ptr() as *const Adc) };
+
+    // The calibration factor can't be set and is written back as it was read.
+    periph.cfgr.set_res(1);
+    assert_eq!(periph.cfgr.cal(), 0xa5);
+    assert_eq!(words[0], 0x1a500);
+}
@@ -0,0 +1,104 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Derived peripherals overriding registers of their base are given a type of their own while
+//! derived registers, fields and enumerations take the definition of their base.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/DERIVED.svd");
+
+use derived::{tim, uart4, usart};
+use derived::tim::{AState, BState, Cc1pPolarity, EModeRead, EModeWrite, Oc1mOcm};
+
+#[test]
+fn test_derived_layout() {
+    assert_eq!(mem::size_of::<usart::Usart>(), 0x8);
+    assert_eq!(mem::offset_of!(uart4::Usart, sr), 0x4);
+    assert_eq!(mem::offset_of!(uart4::Usart, gtpr), 0x10);
+    assert_eq!(mem::size_of::<uart4::Usart>(), 0x14);
+}
+
+#[test]
+fn test_derived_override() {
+    let mut words = [0u32; 5];
+    let periph: &uart4::Usart = unsafe { &*(words.as_mut_ptr() as *const uart4::Usart) };
+
+    periph.cr1.set_over8(true);
+    periph.gtpr.set_psc(0x20);
+    assert!(periph.cr1.over8());
+    assert_eq!(words[0], 0x8000);
+    assert_eq!(words[4], 0x20);
+}
+
+#[test]
+fn test_derived_register() {
+    let mut words = [0u32; 9];
+    let periph: &tim::Tim = unsafe { &*(words.as_mut_ptr() as *const tim::Tim) };
+
+    periph.ccmr2.set_oc1m(Oc1mOcm::Pwm1).set_oc2m(Oc1mOcm::Active);
+    assert!(periph.ccmr2.oc1m() == Some(Oc1mOcm::Pwm1));
+    assert!(periph.ccmr2.oc2m() == Some(Oc1mOcm::Active));
+    assert_eq!(words[7], 0x1060);
+}
+
+#[test]
+fn test_derived_field() {
+    let mut words = [0u32; 9];
+    let periph: &tim::Tim = unsafe { &*(words.as_mut_ptr() as *const tim::Tim) };
+
+    periph.ccer.set_cc2p(Cc1pPolarity::Low).set_cc3p(Cc1pPolarity::Low);
+    assert!(periph.ccer.cc1p() == Some(Cc1pPolarity::High));
+    assert!(periph.ccer.cc3p() == Some(Cc1pPolarity::Low));
+    assert_eq!(words[8], 0x220);
+
+    // The bit offset of the derived field replaces the bit range of its base.
+    words[8] = 0;
+    periph.ccer.set_cc4p(true);
+    assert!(periph.ccer.cc4p());
+    assert!(!periph.ccer.cc4e());
+    assert_eq!(words[8], 0x2000);
+}
+
+#[test]
+fn test_derived_qualified_enumerated_values() {
+    let mut words = [0u32; 9];
+    let periph: &tim::Tim = unsafe { &*(words.as_mut_ptr() as *const tim::Tim) };
+
+    // Enumerations of the same name are told apart by the path of their field.
+    periph.cr.set_c(BState::Auto).set_d(AState::Busy);
+    assert!(periph.cr.c() == Some(BState::Auto));
+    assert!(periph.cr.d() == Some(AState::Busy));
+    assert_eq!(words[0], 0x60);
+}
+
+#[test]
+fn test_derived_read_write_enumerated_values() {
+    let mut words = [0u32; 9];
+    let periph: &tim::Tim = unsafe { &*(words.as_mut_ptr() as *const tim::Tim) };
+
+    // The derived field takes the separate read and write types of its base.
+    periph.cr.set_e(EModeWrite::Start).set_f(EModeWrite::Start);
+    assert_eq!(words[0], 0x500);
+    words[0] = 0x1100;
+    assert!(periph.cr.e() == Some(EModeRead::Busy));
+    assert!(periph.cr.f() == Some(EModeRead::Idle));
+
+    // Values derived for reading alone take the read type.
+    assert!(periph.cr.g() == Some(EModeRead::Busy));
+    periph.cr.set_g(0);
+    assert_eq!(words[0], 0x100);
+}
@@ -0,0 +1,102 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fields with enumerated values are read and written through their enumerations.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/ENUMS.svd");
+
+use enums::uart::{Level, ModeRead, ModeWrite, Parity, Presc, Uart};
+
+#[test]
+fn test_enumerated_value() {
+    let mut words = [0u32; 1];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    periph.cr.set_parity(Parity::Odd);
+    assert!(periph.cr.parity() == Some(Parity::Odd));
+    assert_eq!(periph.cr.parity_bits(), 3);
+    assert_eq!(words[0], 0x30);
+}
+
+#[test]
+fn test_unenumerated_value() {
+    // Values missing from the SVD are read without panicking.
+    let mut words = [0x10u32; 1];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    assert!(periph.cr.parity().is_none());
+    assert_eq!(periph.cr.parity_bits(), 1);
+    assert_eq!(periph.cr.get().parity_bits(), 1);
+}
+
+#[test]
+fn test_read_write_enumerated_values() {
+    let mut words = [0u32; 1];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    // The values written and read back are enumerated separately.
+    periph.cr.set_mode(ModeWrite::Start);
+    assert!(periph.cr.mode() == Some(ModeRead::Busy));
+    periph.cr.set_mode(ModeWrite::Restart);
+    assert!(periph.cr.mode().is_none());
+    assert_eq!(words[0], 0x3);
+}
+
+#[test]
+fn test_read_enumerated_values() {
+    let mut words = [0u32; 1];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    // Without enumerated values to write, the field is written as its bits.
+    periph.cr.set_level(3);
+    assert!(periph.cr.level() == Some(Level::Full));
+    assert_eq!(words[0], 0x300);
+}
+
+#[test]
+fn test_debug() {
+    let mut words = [0x131u32; 1];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    // Values that aren't enumerated are printed as their bits.
+    assert_eq!(format!("{:?}", periph.cr.get()), "CrGet { parity: Odd, mode: Busy, level: 1 }");
+
+    let parity = Parity::Even;
+    let copy = parity;
+    assert_eq!(format!("{:?}", parity), "Even");
+    assert_eq!(copy, Parity::Even);
+}
+
+#[test]
+fn test_default_enumerated_value() {
+    let mut words = [0x25u32; 2];
+    let periph: &Uart = unsafe { &*(words.as_mut_ptr() as *const Uart) };
+
+    // Values that aren't otherwise enumerated are read as the default value, which is written as
+    // the first of them.
+    assert!(periph.mr.presc() == Some(Presc::Reserved));
+    assert_eq!(periph.mr.presc_bits(), 5);
+    periph.mr.set_presc(Presc::Div4);
+    assert!(periph.mr.presc() == Some(Presc::Div4));
+    periph.mr.set_presc(Presc::Reserved);
+    assert_eq!(periph.mr.presc_bits(), 2);
+
+    // Enumerations with only a default value are left as the field's bits.
+    assert_eq!(periph.mr.speed(), 2);
+    periph.mr.set_speed(1);
+    assert_eq!(words[1], 0x12);
+}
@@ -0,0 +1,46 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The interrupts of every peripheral are gathered into the device's `Interrupt` enum.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::convert::TryFrom;
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+// The peripherals declare interrupts alone so the cell goes unused.
+#[allow(dead_code)]
+mod volatile_cell;
+
+svd_mmap!("../../tests/INTERRUPTS.svd");
+
+use interrupts::Interrupt;
+
+#[test]
+fn test_interrupt_nr() {
+    assert_eq!(mem::size_of::<Interrupt>(), 2);
+    assert_eq!(Interrupt::Wwdg.nr(), 0);
+    assert_eq!(Interrupt::Exti1.nr(), 7);
+    assert_eq!(Interrupt::Tim1BrkTim15.nr(), 24);
+
+    // Including those of derived peripherals.
+    assert_eq!(Interrupt::Usart1.nr(), 37);
+    assert_eq!(Interrupt::Usart2.nr(), 38);
+}
+
+#[test]
+fn test_interrupt_try_from() {
+    assert_eq!(Interrupt::try_from(6), Ok(Interrupt::Exti0));
+    assert_eq!(Interrupt::try_from(24), Ok(Interrupt::Tim1BrkTim15));
+    assert_eq!(Interrupt::try_from(1), Err(1));
+    assert_eq!(Interrupt::try_from(39), Err(39));
+}
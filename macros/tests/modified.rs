@@ -0,0 +1,68 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Updates leave the bits cleared by writing one or zero to them unmodified.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/MODIFIED.svd");
+
+use modified::tim::Tim;
+
+#[test]
+fn test_update_leaves_flags() {
+    // Both flags cleared by writing one and the flag cleared by writing zero are pending.
+    let mut words = [0x43u32, 0];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.sr.set_uie(true);
+    assert_eq!(words[0], 0x140);
+}
+
+#[test]
+fn test_clear() {
+    let mut words = [0x43u32, 0];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.sr.clear_uif();
+    assert_eq!(words[0], 0x41);
+
+    periph.sr.clear_tif();
+    assert_eq!(words[0], 0x0);
+
+    periph.icr.clear_uif().clear_cc1if();
+    assert_eq!(words[1], 0x3);
+}
+
+#[test]
+fn test_ignoring_state() {
+    let mut words = [0x43u32, 0];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.sr.ignoring_state().set_uie(true);
+    assert_eq!(words[0], 0x140);
+}
+
+#[test]
+fn test_modify_leaves_flags() {
+    let mut words = [0x43u32, 0];
+    let periph: &Tim = unsafe { &*(words.as_mut_ptr() as *const Tim) };
+
+    periph.sr.modify(|r, w| w.set_uie(r.uif()));
+    assert_eq!(words[0], 0x140);
+
+    periph.sr.write(|w| w.set_uie(true));
+    assert_eq!(words[0], 0x140);
+}
@@ -0,0 +1,52 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers whose reads have side effects are only read explicitly.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/READ_ACTION.svd");
+
+use read_action::usart::Usart;
+
+#[test]
+fn test_read_with_side_effects() {
+    let mut words = [0x41u32, 0x88, 0];
+    let periph: &Usart = unsafe { &*(words.as_mut_ptr() as *const Usart) };
+
+    let sr = periph.sr.read_with_side_effects();
+    assert!(sr.ore());
+    assert!(sr.txe());
+    assert!(!sr.txeie());
+    assert_eq!(periph.dr.read_with_side_effects().data(), 0x41);
+}
+
+#[test]
+fn test_update_from_reset() {
+    let mut words = [0x41u32, 0x88, 0x80];
+    let periph: &Usart = unsafe { &*(words.as_mut_ptr() as *const Usart) };
+
+    // The registers aren't read to update them.
+    periph.dr.set_data(0x42);
+    periph.sr.set_txeie(true);
+    assert_eq!(words[0], 0x42);
+    assert_eq!(words[1], 0x20);
+
+    // Those without read side effects still are.
+    periph.cr.set_ue(true);
+    assert_eq!(words[2], 0x81);
+    assert!(periph.cr.ue());
+    assert!(periph.cr.get().ue());
+}
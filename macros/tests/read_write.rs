@@ -0,0 +1,136 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers are read and written at the call of `read`, `write` and `modify`.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/RESET.svd");
+
+use reset::rcc::Rcc;
+use reset::wdg::Wdg;
+
+#[test]
+fn test_read() {
+    let mut words = [0x163u32, 0, 0];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    // The value read is kept when the register is later written.
+    let r = periph.cr.read();
+    periph.cr.reset();
+    assert!(!periph.cr.hsion());
+    assert!(r.hsion());
+    assert_eq!(r.msirange(), 6);
+}
+
+#[test]
+fn test_write() {
+    let mut words = [0xffff_ffffu32; 3];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    // Fields left alone take their reset value.
+    periph.cr.write(|w| w.set_hsion(true).set_msirange(0xb));
+    assert_eq!(words[0], 0x1b3);
+
+    // Even when none are set.
+    periph.cfgr.write(|w| w);
+    assert_eq!(words[1], 0);
+}
+
+#[test]
+fn test_modify() {
+    let mut words = [0x163u32, 0, 0];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    periph.cr.modify(|r, w| w.set_msirange(r.msirange() + 1).set_hsion(false));
+    assert_eq!(words[0], 0x73);
+}
+
+fn set_sw(periph: &Rcc, sw: Option<u8>) -> Option<()> {
+    // Nothing is written when returning before the write.
+    periph.cfgr.write(|w| w.set_sw(1));
+    let sw = sw?;
+    periph.cfgr.write(|w| w.set_sw(sw));
+    Some(())
+}
+
+#[test]
+fn test_early_return() {
+    let mut words = [0u32; 3];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    assert!(set_sw(periph, None).is_none());
+    assert_eq!(words[1], 1);
+    assert!(set_sw(periph, Some(2)).is_some());
+    assert_eq!(words[1], 2);
+}
+
+#[test]
+fn test_bits() {
+    let mut words = [0x163u32, 0, 0xffff_0c00];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    assert_eq!(periph.cr.read_bits(), 0x163);
+    assert_eq!(periph.cr.read().bits(), 0x163);
+    assert_eq!(periph.csr.read_bits(), 0x0c00u16);
+
+    unsafe { periph.cr.write_bits(0xdead_0000) };
+    assert_eq!(words[0], 0xdead_0000);
+
+    // Raw bits set on an update replace the whole register, including the fields set before.
+    {
+        let mut update = periph.cfgr.set_sw(2);
+        assert_eq!(update.bits(), 2);
+        unsafe { update.set_bits(0x8000_0001) };
+        assert_eq!(update.bits(), 0x8000_0001);
+    }
+    assert_eq!(words[1], 0x8000_0001);
+
+    periph.csr.write(|w| unsafe { w.set_bits(0xffff) });
+    assert_eq!(words[2], 0xffff_ffff);
+}
+
+#[test]
+fn test_debug() {
+    let mut words = [0x163u32, 0, 0];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    assert_eq!(format!("{:?}", periph.cr.read()),
+               "CrGet { msion: true, msirdy: true, msirange: 6, hsion: true }");
+}
+
+#[test]
+fn test_renamed_read_write() {
+    let mut words = [0u64; 2];
+    let periph: &Wdg = unsafe { &*(words.as_mut_ptr() as *const Wdg) };
+
+    // Fields named like the register's methods keep their names while the methods gain an
+    // underscore.
+    periph.kr.write_(|w| w.set_read(true).set_bits(0xa));
+    assert_eq!(words[0] >> 32, 0xa1);
+
+    periph.kr.modify_(|r, w| w.set_write(r.read()).set_modify(r.bits() == 0xa));
+    let r = periph.kr.read_();
+    assert!(r.read() && r.write() && r.modify());
+    assert_eq!(r.bits_(), 0xa7);
+    assert_eq!(periph.kr.read_bits(), 0xa7);
+
+    let mut w = periph.kr.ignoring_state();
+    unsafe { w.set_bits_(0x12) };
+    assert_eq!(w.bits_(), 0x12);
+    drop(w);
+    assert_eq!(words[0] >> 32, 0x12);
+    assert_eq!(periph.kr.bits(), 0x1);
+}
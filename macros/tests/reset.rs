@@ -0,0 +1,74 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers know their reset value and updates ignoring their state start from it.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/RESET.svd");
+
+use reset::rcc::{Cfgr, Cr, Csr, Rcc};
+use reset::wdg::{self, Cnt, Wdg};
+
+#[test]
+fn test_reset_constants() {
+    assert_eq!(Cr::RESET_VALUE, 0x63);
+    assert_eq!(Cr::RESET_MASK, 0xffff_ffff);
+    assert_eq!(Cfgr::RESET_VALUE, 0);
+    assert_eq!(Csr::RESET_VALUE, 0x0c00u16);
+    assert_eq!(Csr::RESET_MASK, 0x0fffu16);
+    assert_eq!(Cnt::RESET_VALUE, 0x1_0000_0000u64);
+    assert_eq!(Cnt::RESET_MASK, !0u64);
+}
+
+#[test]
+fn test_reset() {
+    let mut words = [0xffff_ffffu32; 3];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    periph.cr.reset();
+    periph.cfgr.reset();
+    periph.csr.reset();
+    assert_eq!(words, [0x63, 0, 0xffff_0c00]);
+}
+
+#[test]
+fn test_ignoring_state_from_reset() {
+    let mut words = [0xffff_ffffu32; 3];
+    let periph: &Rcc = unsafe { &*(words.as_mut_ptr() as *const Rcc) };
+
+    // Fields left alone take their reset value rather than zero or their current value.
+    periph.cr.ignoring_state().set_hsion(true);
+    assert_eq!(words[0], 0x163);
+
+    periph.cr.ignoring_state().set_msirange(0xb);
+    assert_eq!(periph.cr.msirange(), 0xb);
+    assert!(periph.cr.msion());
+    assert!(!periph.cr.hsion());
+}
+
+#[test]
+fn test_reset_field() {
+    let mut words = [0xffff_ffff_ffff_ffffu64; 2];
+    let periph: &Wdg = unsafe { &*(words.as_mut_ptr() as *const Wdg) };
+
+    // The reset method gives way to the getter of the RESET field.
+    assert!(periph.cr.reset());
+    periph.cr.reset_();
+    periph.cnt.reset();
+    assert!(!periph.cr.reset());
+    assert_eq!(words, [0xffff_ffff_0000_0002, 0x1_0000_0000]);
+    assert_eq!(wdg::Cr::RESET_VALUE, 2);
+}
@@ -0,0 +1,101 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registers of each size are accessed with a cell of the same width.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/SIZES.svd");
+
+use sizes::byte::Byte;
+use sizes::double::Double;
+use sizes::half::Half;
+use sizes::mixed::Mixed;
+use sizes::wide::{Sel, Wide};
+use sizes::word::Word;
+
+#[test]
+fn test_register_sizes() {
+    assert_eq!(mem::size_of::<Byte>(), 1);
+    assert_eq!(mem::size_of::<Half>(), 2);
+    assert_eq!(mem::size_of::<Word>(), 4);
+    assert_eq!(mem::size_of::<Double>(), 8);
+}
+
+#[test]
+fn test_byte_register() {
+    let mut byte: [u8; 2] = [0, 0x5a];
+    let periph: &Byte = unsafe { &*(byte.as_mut_ptr() as *const Byte) };
+
+    periph.dr.set_lo(0xf).set_hi(0x3);
+    assert_eq!(periph.dr.lo(), 0xf);
+    assert_eq!(periph.dr.hi(), 0x3);
+
+    // The neighbouring byte is left alone.
+    assert_eq!(byte, [0x3f, 0x5a]);
+}
+
+#[test]
+fn test_half_register() {
+    let mut half: [u16; 2] = [0, 0xa5a5];
+    let periph: &Half = unsafe { &*(half.as_mut_ptr() as *const Half) };
+
+    periph.cr.set_presc(0xff).set_en(true);
+    assert_eq!(periph.cr.presc(), 0xff);
+    assert!(periph.cr.en());
+    assert_eq!(half, [0xff01, 0xa5a5]);
+}
+
+#[test]
+fn test_word_register() {
+    let periph: Word = unsafe { mem::transmute(0u32) };
+
+    periph.sr.set_count(0xffff);
+    assert_eq!(periph.sr.count(), 0xffff);
+}
+
+#[test]
+fn test_double_register() {
+    let periph: Double = unsafe { mem::transmute(0u64) };
+
+    periph.cnt.set_hi(0xdead_beef).set_lo(0x0123_4568);
+    assert_eq!(periph.cnt.hi(), 0xdead_beef);
+    assert_eq!(periph.cnt.lo(), 0x0123_4568);
+    assert_eq!(periph.cnt.all(), 0xdead_beef_0123_4568);
+}
+
+#[test]
+fn test_double_register_enum() {
+    let periph: Wide = unsafe { mem::transmute(0u64) };
+
+    // Enumerated values of fields in 64-bit registers may be beyond 32 bits.
+    assert_eq!(mem::size_of::<Sel>(), 8);
+    periph.mode.set_sel(Sel::Far);
+    assert_eq!(periph.mode.sel(), Some(Sel::Far));
+    assert_eq!(periph.mode.read_bits(), 0x80_0000_0000 << 8);
+}
+
+#[test]
+fn test_mixed_register_layout() {
+    // Padding follows the size of each register rather than assuming 32 bits.
+    assert_eq!(mem::offset_of!(Mixed, a), 0x0);
+    assert_eq!(mem::offset_of!(Mixed, b), 0x1);
+    assert_eq!(mem::offset_of!(Mixed, c), 0x2);
+    assert_eq!(mem::offset_of!(Mixed, d), 0x4);
+    assert_eq!(mem::offset_of!(Mixed, e), 0x8);
+    assert_eq!(mem::offset_of!(Mixed, f), 0x14);
+    assert_eq!(mem::size_of::<Mixed>(), 0x18);
+}
@@ -0,0 +1,42 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/STM32L4x6.svd");
+
+use stm32l4x6::spi1::Spi1;
+
+#[test]
+fn test_spi1_layout() {
+    assert_eq!(mem::size_of::<Spi1>(), 4);
+}
+
+#[test]
+fn test_spi1_update() {
+    let spi1: Spi1 = unsafe { mem::transmute(0u32) };
+
+    spi1.cr1.set_lsbfirst(true).set_cpol(true).set_cpha(true);
+    assert!(spi1.cr1.lsbfirst());
+    assert!(spi1.cr1.cpol());
+    assert!(spi1.cr1.cpha());
+    assert!(!spi1.cr1.spe());
+
+    spi1.cr1.set_spe(true);
+    let cr1 = spi1.cr1.get();
+    assert!(cr1.spe());
+    assert!(cr1.lsbfirst());
+}
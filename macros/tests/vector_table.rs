@@ -0,0 +1,52 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The vector table holds the handler of each interrupt at its number.
+
+#![allow(non_snake_case)]
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+// The peripherals declare interrupts alone so the cell goes unused.
+#[allow(dead_code)]
+mod volatile_cell;
+
+svd_mmap!("../../tests/INTERRUPTS.svd", vector_table);
+
+use interrupts::{Interrupt, __INTERRUPTS};
+
+// Without the linker script providing `DefaultHandler` every handler must be defined.
+#[no_mangle] pub extern "C" fn WWDG() {}
+#[no_mangle] pub extern "C" fn EXTI0() {}
+#[no_mangle] pub extern "C" fn EXTI1() {}
+#[no_mangle] pub extern "C" fn TIM1_BRK_TIM15() {}
+#[no_mangle] pub extern "C" fn USART1() {}
+#[no_mangle] pub extern "C" fn USART2() {}
+
+#[test]
+fn test_vector_table() {
+    let vectors: &[usize; 39] = unsafe { mem::transmute(&__INTERRUPTS) };
+
+    assert_eq!(vectors[Interrupt::Wwdg.nr() as usize], WWDG as *const () as usize);
+    assert_eq!(vectors[Interrupt::Exti0.nr() as usize], EXTI0 as *const () as usize);
+    assert_eq!(vectors[Interrupt::Exti1.nr() as usize], EXTI1 as *const () as usize);
+    assert_eq!(vectors[Interrupt::Tim1BrkTim15.nr() as usize],
+               TIM1_BRK_TIM15 as *const () as usize);
+    assert_eq!(vectors[Interrupt::Usart1.nr() as usize], USART1 as *const () as usize);
+    assert_eq!(vectors[Interrupt::Usart2.nr() as usize], USART2 as *const () as usize);
+
+    // Gaps in the interrupt numbers are reserved.
+    assert_eq!(vectors[1], 0);
+    assert_eq!(vectors[36], 0);
+}
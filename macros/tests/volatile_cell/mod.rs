@@ -0,0 +1,29 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Host stand-in for the volatile_cell crate used by firmware.
+
+use std::cell::UnsafeCell;
+use std::ptr;
+
+#[repr(C)]
+pub struct VolatileCell<T> {
+    value: UnsafeCell<T>,
+}
+
+impl<T: Copy> VolatileCell<T> {
+    pub fn get(&self) -> T {
+        unsafe { ptr::read_volatile(self.value.get()) }
+    }
+
+    pub fn set(&self, value: T) {
+        unsafe { ptr::write_volatile(self.value.get(), value) }
+    }
+}
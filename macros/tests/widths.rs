@@ -0,0 +1,229 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Round trip fields of every width at every offset through the generated Get and Update types.
+
+extern crate core;
+extern crate svd_mmap_macros;
+
+use std::mem;
+use svd_mmap_macros::svd_mmap;
+
+mod volatile_cell;
+
+svd_mmap!("../../tests/WIDTHS.svd");
+
+use widths::fields::Fields;
+
+/// Bits surrounding the field under test, which updates of the field leave as they are. Both
+/// bit 0 and bit 31 are set so that neither end of the register is assumed to be clear.
+const BACKGROUND: u32 = 0xa5a5_a5a5;
+
+/// Conversion between the generated field types and their raw bits.
+trait Bits {
+    fn from_bits(bits: u32) -> Self;
+    fn to_bits(self) -> u32;
+}
+
+impl Bits for bool {
+    fn from_bits(bits: u32) -> bool { bits != 0 }
+    fn to_bits(self) -> u32 { self as u32 }
+}
+
+impl Bits for u8 {
+    fn from_bits(bits: u32) -> u8 { bits as u8 }
+    fn to_bits(self) -> u32 { self as u32 }
+}
+
+impl Bits for u16 {
+    fn from_bits(bits: u32) -> u16 { bits as u16 }
+    fn to_bits(self) -> u32 { self as u32 }
+}
+
+impl Bits for u32 {
+    fn from_bits(bits: u32) -> u32 { bits }
+    fn to_bits(self) -> u32 { self }
+}
+
+/// Check that each field reads back what was written and leaves its neighbours untouched.
+macro_rules! round_trip {
+    ($reg:expr, $width:expr, [$(($get:ident, $set:ident, $offset:expr)),*]) => {$(
+        let mask = ((1u64 << $width) - 1) as u32;
+        let pattern = 0x5555_5555 & mask;
+
+        $reg.ignoring_state().set_all(BACKGROUND);
+        $reg.$set(Bits::from_bits(mask));
+        assert_eq!($reg.$get().to_bits(), mask);
+        assert_eq!($reg.all(), BACKGROUND & !(mask << $offset) | mask << $offset);
+
+        $reg.$set(Bits::from_bits(0));
+        assert_eq!($reg.$get().to_bits(), 0);
+        assert_eq!($reg.all(), BACKGROUND & !(mask << $offset));
+
+        $reg.ignoring_state().$set(Bits::from_bits(pattern));
+        assert_eq!($reg.get().$get().to_bits(), pattern);
+        assert_eq!($reg.all(), pattern << $offset);
+    )*};
+}
+
+#[test]
+fn test_round_trip_all_widths() {
+    let fields: Fields = unsafe { mem::transmute([0u32; 32]) };
+
+    round_trip!(fields.w1, 1, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26), (o27, set_o27, 27),
+                (o28, set_o28, 28), (o29, set_o29, 29), (o30, set_o30, 30), (o31, set_o31, 31)]);
+    round_trip!(fields.w2, 2, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26), (o27, set_o27, 27),
+                (o28, set_o28, 28), (o29, set_o29, 29), (o30, set_o30, 30)]);
+    round_trip!(fields.w3, 3, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26), (o27, set_o27, 27),
+                (o28, set_o28, 28), (o29, set_o29, 29)]);
+    round_trip!(fields.w4, 4, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26), (o27, set_o27, 27),
+                (o28, set_o28, 28)]);
+    round_trip!(fields.w5, 5, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26), (o27, set_o27, 27)]);
+    round_trip!(fields.w6, 6, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25), (o26, set_o26, 26)]);
+    round_trip!(fields.w7, 7, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24), (o25, set_o25, 25)]);
+    round_trip!(fields.w8, 8, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23),
+                (o24, set_o24, 24)]);
+    round_trip!(fields.w9, 9, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2), (o3, set_o3, 3),
+                (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6), (o7, set_o7, 7),
+                (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10), (o11, set_o11, 11),
+                (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14), (o15, set_o15, 15),
+                (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18), (o19, set_o19, 19),
+                (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22), (o23, set_o23, 23)]);
+    round_trip!(fields.w10, 10, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18),
+                (o19, set_o19, 19), (o20, set_o20, 20), (o21, set_o21, 21), (o22, set_o22, 22)]);
+    round_trip!(fields.w11, 11, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18),
+                (o19, set_o19, 19), (o20, set_o20, 20), (o21, set_o21, 21)]);
+    round_trip!(fields.w12, 12, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18),
+                (o19, set_o19, 19), (o20, set_o20, 20)]);
+    round_trip!(fields.w13, 13, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18),
+                (o19, set_o19, 19)]);
+    round_trip!(fields.w14, 14, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17), (o18, set_o18, 18)]);
+    round_trip!(fields.w15, 15, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16), (o17, set_o17, 17)]);
+    round_trip!(fields.w16, 16, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15), (o16, set_o16, 16)]);
+    round_trip!(fields.w17, 17, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14),
+                (o15, set_o15, 15)]);
+    round_trip!(fields.w18, 18, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13), (o14, set_o14, 14)]);
+    round_trip!(fields.w19, 19, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12), (o13, set_o13, 13)]);
+    round_trip!(fields.w20, 20, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11), (o12, set_o12, 12)]);
+    round_trip!(fields.w21, 21, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10),
+                (o11, set_o11, 11)]);
+    round_trip!(fields.w22, 22, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9), (o10, set_o10, 10)]);
+    round_trip!(fields.w23, 23, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8), (o9, set_o9, 9)]);
+    round_trip!(fields.w24, 24, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7), (o8, set_o8, 8)]);
+    round_trip!(fields.w25, 25, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6),
+                (o7, set_o7, 7)]);
+    round_trip!(fields.w26, 26, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5), (o6, set_o6, 6)]);
+    round_trip!(fields.w27, 27, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4), (o5, set_o5, 5)]);
+    round_trip!(fields.w28, 28, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3), (o4, set_o4, 4)]);
+    round_trip!(fields.w29, 29, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2),
+                (o3, set_o3, 3)]);
+    round_trip!(fields.w30, 30, [(o0, set_o0, 0), (o1, set_o1, 1), (o2, set_o2, 2)]);
+    round_trip!(fields.w31, 31, [(o0, set_o0, 0), (o1, set_o1, 1)]);
+    round_trip!(fields.w32, 32, [(o0, set_o0, 0)]);
+}
@@ -0,0 +1,153 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of the memory map from a cargo build script.
+
+use inflections::Inflect;
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use svd::Device;
+use {gen_link_mem, gen_source, Options};
+
+/// Generate the memory map of a SVD file into files for use from a `build.rs`.
+///
+/// ```rust,no_run
+/// extern crate svd_mmap;
+///
+/// fn main() {
+///     svd_mmap::Generator::new("STM32L4x6.svd").generate().unwrap();
+/// }
+/// ```
+///
+/// The device module is written to `$OUT_DIR/<device>.rs` where it can be brought into the crate
+/// with `include!(concat!(env!("OUT_DIR"), "/stm32l4x6.rs"));`. The symbols the generated `extern`
+/// statics link against are written to `$OUT_DIR/<device>.ld` which is handed to the linker.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    svd: PathBuf,
+    out_dir: Option<PathBuf>,
+    options: Options,
+}
+
+impl Generator {
+    /// Create a generator for the given SVD file.
+    pub fn new<P: AsRef<Path>>(svd: P) -> Generator {
+        Generator {
+            svd: svd.as_ref().to_path_buf(),
+            out_dir: None,
+            options: Options::default(),
+        }
+    }
+
+    /// Set the directory the generated files are written to.
+    ///
+    /// Defaults to the `OUT_DIR` cargo provides to build scripts.
+    pub fn out_dir<P: AsRef<Path>>(mut self, out_dir: P) -> Generator {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generate the Cortex-M vector table of the device's interrupts. The linker symbols then
+    /// provide `DefaultHandler` for each interrupt handler the application doesn't define.
+    pub fn vector_table(mut self, vector_table: bool) -> Generator {
+        self.options.vector_table = vector_table;
+        self
+    }
+
+    /// Assign the linker symbols of the peripherals with `PROVIDE` so that they may be defined
+    /// elsewhere instead.
+    pub fn provide_link_symbols(mut self, provide_link_symbols: bool) -> Generator {
+        self.options.provide_link_symbols = provide_link_symbols;
+        self
+    }
+
+    /// Reach each peripheral through a constant pointer to its base address rather than a linker
+    /// symbol.
+    pub fn const_addresses(mut self, const_addresses: bool) -> Generator {
+        self.options.const_addresses = const_addresses;
+        self
+    }
+
+    /// Write the generated Rust and linker symbols and print the directives telling cargo to
+    /// rebuild when the SVD file changes and to link against the symbols.
+    pub fn generate(&self) -> io::Result<()> {
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR").map(PathBuf::from).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set")
+            })?,
+        };
+
+        let mut s = String::new();
+        File::open(&self.svd)?.read_to_string(&mut s)?;
+
+        // Generate SVD device data from SVD XML.
+        let dev = Device::parse(&s);
+        let name = dev.name.to_snake_case();
+
+        let mut rs = File::create(out_dir.join(name.clone() + ".rs"))?;
+        rs.write_all(gen_source(&dev, &self.options).as_bytes())?;
+
+        let ld_path = out_dir.join(name + ".ld");
+        gen_link_mem(&dev, &self.options, &mut File::create(&ld_path)?)?;
+
+        println!("cargo:rerun-if-changed={}", self.svd.display());
+        println!("cargo:rustc-link-arg={}", ld_path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+    use super::Generator;
+
+    #[test]
+    fn test_generate() {
+        let svd = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/STM32L4x6.svd");
+        let out_dir = env::temp_dir().join("svd-mmap-test-generate");
+        fs::create_dir_all(&out_dir).unwrap();
+
+        Generator::new(svd).out_dir(&out_dir).generate().unwrap();
+
+        let mut rs = String::new();
+        File::open(out_dir.join("stm32l4x6.rs")).unwrap().read_to_string(&mut rs).unwrap();
+        assert!(rs.contains("pub mod stm32l4x6"));
+        assert!(rs.contains("pub mod spi1"));
+
+        let mut ld = String::new();
+        File::open(out_dir.join("stm32l4x6.ld")).unwrap().read_to_string(&mut ld).unwrap();
+        assert_eq!(ld, "mmap_stm32l4x6_spi1 = 0x40013000;\n");
+    }
+
+    #[test]
+    fn test_generate_vector_table() {
+        let svd = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/INTERRUPTS.svd");
+        let out_dir = env::temp_dir().join("svd-mmap-test-generate-vector-table");
+        fs::create_dir_all(&out_dir).unwrap();
+
+        Generator::new(svd).out_dir(&out_dir).vector_table(true).generate().unwrap();
+
+        let mut rs = String::new();
+        File::open(out_dir.join("interrupts.rs")).unwrap().read_to_string(&mut rs).unwrap();
+        assert!(rs.contains("#[link_section = \".vector_table.interrupts\"]"));
+
+        let mut ld = String::new();
+        File::open(out_dir.join("interrupts.ld")).unwrap().read_to_string(&mut ld).unwrap();
+        assert!(ld.contains("\nPROVIDE(WWDG = DefaultHandler);\n"));
+        assert!(ld.ends_with("\nPROVIDE(USART2 = DefaultHandler);\n"));
+    }
+}
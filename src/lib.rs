@@ -11,61 +11,121 @@
 // Author: Brandon Edens <brandonedens@gmail.com>
 // Date: 2016-08-15
 
-//! Provide a Rust macro for converting ARM CMSIS SVD description into Rust for accessing the
-//! specified hardware.
+//! Convert an ARM CMSIS SVD description into Rust for accessing the specified hardware.
+//!
+//! The generators produce token streams which the `svd_mmap!` procedural macro in the
+//! svd-mmap-macros crate expands to directly, while `gen_source` renders them as formatted Rust
+//! for the command line tool and build scripts.
 
-#![feature(plugin, plugin_registrar, rustc_private)]
-#![plugin(quasi_macros)]
-
-extern crate aster;
 extern crate inflections;
-extern crate quasi;
-extern crate rustc;
-extern crate rustc_plugin;
-extern crate svd_parser as svd;
-extern crate syntax;
+extern crate prettyplease;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+extern crate xmltree;
+
+mod generator;
+pub mod svd;
+
+pub use generator::Generator;
 
 use inflections::Inflect;
-use rustc_plugin::Registry;
-use std::borrow::Borrow;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
-use std::path::PathBuf;
-use svd::{Access, Device, Field, Peripheral, Register};
-use syntax::ast;
-use syntax::codemap::Span;
-use syntax::ext::base::{DummyResult, ExtCtxt, MacResult};
-use syntax::parse::token;
-use syntax::ptr::P;
-use syntax::tokenstream;
-use syntax::util::small_vector::SmallVector;
-
-const LINK_MEM_PREFIX: &'static str = "mmap_";
+use std::convert::TryFrom;
+use std::io;
+use std::ptr;
+use svd::{Access, Cluster, Device, Dim, EnumeratedValue, EnumeratedValues, Field, Interrupt,
+          ModifiedWriteValues, Peripheral, Register, Usage, WriteConstraint};
+
+const LINK_MEM_PREFIX: &str = "mmap_";
+
+/// Choices about the software generated for a device.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Generate the Cortex-M vector table of the device's interrupts and, in the linker script,
+    /// alias each interrupt handler not otherwise defined to `DefaultHandler`.
+    pub vector_table: bool,
+    /// Assign the linker symbols of the peripherals with `PROVIDE`, so that they are only defined
+    /// when nothing else defines them.
+    pub provide_link_symbols: bool,
+    /// Reach each peripheral through a constant pointer to its base address, rather than an
+    /// `extern` static whose address is given by the linker script.
+    pub const_addresses: bool,
+}
+
+/// Build an identifier for use within generated software.
+fn id(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+/// Build the doc attribute of an SVD description, if it has one. Runs of whitespace, including
+/// the line breaks of the SVD file, are collapsed and characters that rustdoc would otherwise
+/// read as links, HTML or code are escaped.
+fn doc(description: Option<&str>) -> TokenStream {
+    let text = description.unwrap_or("").split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return TokenStream::new();
+    }
+
+    // The leading space separates the text from the `///` the attribute is rendered as.
+    let mut escaped = String::from(" ");
+    for c in text.chars() {
+        if let '\\' | '[' | ']' | '<' | '>' | '`' | '*' | '_' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    quote!(#[doc = #escaped])
+}
 
 trait GenField {
     /// Generate getter impl.
-    fn gen_get(&self, cx: &ExtCtxt, register: &Register) -> Vec<P<syntax::ast::Item>>;
+    fn gen_get(&self, register: &Register) -> Vec<TokenStream>;
+
+    /// Generate type of the field when it is read or written.
+    fn gen_type(&self, usage: Usage) -> TokenStream;
 
-    /// Generate type of the field.
-    fn gen_type(&self) -> syntax::ptr::P<syntax::ast::Ty>;
+    /// Generate the unsigned type holding the raw bits of the field.
+    fn gen_bits_type(&self) -> TokenStream;
 
-    /// Generate the type definition for the field that has enumerated values.
-    fn gen_type_def(&self, cx: &ExtCtxt) -> Option<P<syntax::ast::Item>>;
+    /// Generate the type definitions for the field that has enumerated values.
+    fn gen_type_def(&self, register: &Register) -> Option<TokenStream>;
 
-    /// Generate type identifier.
-    fn gen_type_ident(&self) -> Option<ast::Ident>;
+    /// Generate the type identifier of the values read or written.
+    fn gen_type_ident(&self, usage: Usage) -> Option<Ident>;
+
+    /// Generate the enum of a set of the field's enumerated values.
+    fn gen_enum(&self, register: &Register, enum_vals: &EnumeratedValues, name: &Ident)
+                -> TokenStream;
+
+    /// The enumerated values read or written.
+    fn enumerated_values(&self, usage: Usage) -> Option<&EnumeratedValues>;
+
+    /// The `isDefault` value of a set of the field's enumerated values along with the value it is
+    /// written as.
+    fn default_value<'a>(&self, enum_vals: &'a EnumeratedValues)
+                         -> Option<(&'a EnumeratedValue, u64)>;
 
     /// Generate setter impl.
-    fn gen_update(&self, cx: &ExtCtxt, register: &Register) -> Vec<P<syntax::ast::Item>>;
+    fn gen_update(&self, register: &Register) -> Vec<TokenStream>;
+
+    /// Generate the impl clearing a field whose bits are cleared by writing one or zero to them.
+    fn gen_clear(&self, register: &Register) -> Vec<TokenStream>;
+
+    /// The effect of writing to the field, which is taken from its register when not given.
+    fn modified_write_values(&self, register: &Register) -> Option<ModifiedWriteValues>;
+
+    /// Mask of the field's bits before they are shifted into position.
+    fn mask(&self) -> u64;
 }
 
 impl GenField for Field {
 
     /// Generate struct representation of register field getter in the form:
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// #[allow(dead_code, missing_docs)]
     /// impl Cr {
     ///     #[inline(always)]
@@ -82,70 +142,128 @@ impl GenField for Field {
     ///     }
     /// }
     /// ```
-    fn gen_get(&self, cx: &ExtCtxt, register: &Register) -> Vec<P<syntax::ast::Item>> {
-        let builder    = aster::AstBuilder::new();
-        let field_name = builder.id(self.name.to_snake_case());
-        let field_ty   = self.gen_type();
+    ///
+    /// Registers whose reads have side effects only have the getter of `CrGet`, as the one of
+    /// `Cr` would read the register again for each field.
+    fn gen_get(&self, register: &Register) -> Vec<TokenStream> {
+        let field_name = id(&self.name.to_snake_case());
+        let field_ty   = match self.gen_type_ident(Usage::Read) {
+            // Values the SVD doesn't enumerate are read as None.
+            Some(enum_name) => quote!(::core::option::Option<#enum_name>),
+            None => self.gen_type(Usage::Read),
+        };
         let bit_offset = self.bit_range.offset;
-        let bit_width  = self.bit_range.width;
+        let bit_mask   = register.value_lit(self.mask());
+
+        let field_doc  = doc(self.description.as_deref());
 
         let reg_name_get = register.getter_name();
         let reg_type_name = register.type_name();
+        let new = register.method_name("new");
 
         let mut v = Vec::new();
-        v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl $reg_type_name {
-                            #[inline(always)]
-                            pub fn $field_name(&self) -> $field_ty {
-                                $reg_name_get::new(self).$field_name()
-                            }
-                        }).unwrap());
+        if !register.read_side_effects() {
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #field_ty {
+                            #reg_name_get::#new(self).#field_name()
+                        }
+                    }
+                });
+        }
 
         v.push(
-            if let Some(enum_vals) = self.enumerated_values.as_ref() {
+            if let Some(enum_vals) = self.enumerated_values(Usage::Read) {
                 let keys = enum_vals.values.iter()
-                    .map(|x| builder.id(x.name.to_pascal_case()))
-                    .collect::<Vec<_>>().into_iter();
+                    .filter(|x| x.value.is_some())
+                    .map(|x| id(&x.name.to_pascal_case()));
                 let vals = enum_vals.values.iter()
-                    .map(|x| x.value)
-                    .collect::<Vec<_>>().into_iter();
-
-                let enum_name = self.gen_type_ident().unwrap();
-
-                quote_item!(&cx,
-                            #[allow(dead_code, missing_docs)]
-                            impl $reg_name_get {
-                                #[inline(always)]
-                                pub fn $field_name(&self) -> $field_ty {
-                                    match (self.value >> $bit_offset) & $bit_width {
-                                        $($vals => ::core::option::Option::Some($enum_name::$keys)),*,
-                                        _ => ::core::option::Option::None,
-                                    }.unwrap()
-                                }
-                            }).unwrap()
+                    .filter_map(|x| x.value)
+                    .map(|x| register.value_lit(x));
+
+                let enum_name = self.gen_type_ident(Usage::Read).unwrap();
+                let default = match self.default_value(enum_vals) {
+                    Some((default, _)) => {
+                        let key = id(&default.name.to_pascal_case());
+                        quote!(::core::option::Option::Some(#enum_name::#key))
+                    }
+                    None => quote!(::core::option::Option::None),
+                };
+
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_name_get {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #field_ty {
+                            match (self.value >> #bit_offset) & #bit_mask {
+                                #(#vals => ::core::option::Option::Some(#enum_name::#keys),)*
+                                _ => #default,
+                            }
+                        }
+                    }
+                }
 
             } else if self.bit_range.width == 1 {
-                quote_item!(&cx,
-                            #[allow(dead_code, missing_docs)]
-                            impl $reg_name_get {
-                                #[inline(always)]
-                                pub fn $field_name(&self) -> $field_ty {
-                                    (self.value >> $bit_offset) & $bit_width != 0
-                                }
-                            }).unwrap()
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_name_get {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #field_ty {
+                            (self.value >> #bit_offset) & #bit_mask != 0
+                        }
+                    }
+                }
 
             } else {
-                quote_item!(&cx,
-                            #[allow(dead_code, missing_docs)]
-                            impl $reg_name_get {
-                                #[inline(always)]
-                                pub fn $field_name(&self) -> $field_ty {
-                                    ((self.value >> $bit_offset) & $bit_width) as $field_ty
-                                }
-                            }).unwrap()
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_name_get {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name(&self) -> #field_ty {
+                            ((self.value >> #bit_offset) & #bit_mask) as #field_ty
+                        }
+                    }
+                }
             });
+
+        // Enumerated fields can also be read as their raw bits.
+        if self.enumerated_values(Usage::Read).is_some() {
+            let bits_name = id(&(self.name.to_snake_case() + "_bits"));
+            let bits_ty = self.gen_bits_type();
+
+            if !register.read_side_effects() {
+                v.push(
+                    quote! {
+                        #[allow(dead_code, missing_docs)]
+                        impl #reg_type_name {
+                            #field_doc
+                            #[inline(always)]
+                            pub fn #bits_name(&self) -> #bits_ty {
+                                #reg_name_get::#new(self).#bits_name()
+                            }
+                        }
+                    });
+            }
+
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_name_get {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #bits_name(&self) -> #bits_ty {
+                            ((self.value >> #bit_offset) & #bit_mask) as #bits_ty
+                        }
+                    }
+                });
+        }
         v
     }
 
@@ -153,79 +271,166 @@ impl GenField for Field {
     ///
     /// A type could be bool, u8, u16, or some enum like Parity depending upon the bit width and
     /// potential existence of enumerated values.
-    fn gen_type(&self) -> syntax::ptr::P<syntax::ast::Ty> {
-        let builder = aster::AstBuilder::new();
-
-        if self.enumerated_values.is_some() {
-            builder.ty().id(self.gen_type_ident().unwrap())
+    fn gen_type(&self, usage: Usage) -> TokenStream {
+        if let Some(name) = self.gen_type_ident(usage) {
+            quote!(#name)
+        } else if self.bit_range.width == 1 {
+            quote!(bool)
         } else {
-            match self.bit_range.width {
-                1 => builder.ty().bool(),
-                2...8 => builder.ty().u8(),
-                9...16 => builder.ty().u16(),
-                17...32 => builder.ty().u32(),
-                33...64 => builder.ty().u64(),
-                _ => panic!("Unknown bit width"),
-            }
+            self.gen_bits_type()
+        }
+    }
+
+    /// Generate the smallest unsigned type holding the field's bits.
+    fn gen_bits_type(&self) -> TokenStream {
+        match self.bit_range.width {
+            1..=8 => quote!(u8),
+            9..=16 => quote!(u16),
+            17..=32 => quote!(u32),
+            33..=64 => quote!(u64),
+            _ => panic!("Unknown bit width"),
         }
     }
 
     /// Generate a type for this field if applicable in the form of:
     ///
-    /// ```rust
-	/// #[derive(PartialEq)]
-	/// #[allow(dead_code, missing_docs)]
-	/// #[repr(u32)]
-	/// pub enum Parity {
-	///     None = 0,
-	///     Even = 2,
-	///     Odd = 3,
-	/// }
+    /// ```rust,ignore
+    /// #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// #[allow(dead_code, missing_docs)]
+    /// #[repr(u32)]
+    /// pub enum Parity {
+    ///     None = 0,
+    ///     Even = 2,
+    ///     Odd = 3,
+    /// }
     /// ```
-    fn gen_type_def(&self, cx: &ExtCtxt) -> Option<P<syntax::ast::Item>> {
-		if self.enumerated_values.is_none() {
-            return None;
-        }
+    ///
+    /// The values read and written are given separate types when the SVD enumerates them
+    /// separately. The enum is represented by the unsigned type of the register's value.
+    fn gen_type_def(&self, register: &Register) -> Option<TokenStream> {
+        let read = self.enumerated_values(Usage::Read);
+        let write = self.enumerated_values(Usage::Write)
+            .filter(|&x| !read.is_some_and(|r| ptr::eq(r, x)));
+
+        let defs: Vec<TokenStream> = [(read, Usage::Read), (write, Usage::Write)].iter()
+            .filter_map(|&(enum_vals, usage)| {
+                let enum_vals = enum_vals?;
+                // The type of the field these values are derived from is reused.
+                if enum_vals.base_field.is_some() {
+                    return None;
+                }
+                Some(self.gen_enum(register, enum_vals, &self.gen_type_ident(usage).unwrap()))
+            })
+            .collect();
 
-        let builder = aster::AstBuilder::new();
-        let enum_vals = self.enumerated_values.as_ref().unwrap();
-        let name = self.gen_type_ident().unwrap();
-
-        let keys = enum_vals.values.iter()
-            .map(|x| builder.id(x.name.to_pascal_case()))
-            .collect::<Vec<_>>().into_iter();
-        let vals = enum_vals.values.iter()
-            .map(|x| x.value)
-            .collect::<Vec<_>>().into_iter();
-
-        Some(quote_item!(&cx,
-                         #[derive(PartialEq)]
-                         #[allow(dead_code, missing_docs)]
-                         #[repr(u32)]
-                         pub enum $name {
-                             $($keys = $vals),*
-                         }).unwrap())
-    }
-
-    /// Generate type identifier.
-    fn gen_type_ident(&self) -> Option<ast::Ident> {
-        if let Some(enum_vals) = self.enumerated_values.as_ref() {
-            let ref name = if let Some(enum_name) = enum_vals.name.as_ref() {
-                self.name.to_owned() + "_" + enum_name
-            } else {
-                self.name.to_owned()
-            };
-            let builder = aster::AstBuilder::new();
-            Some(builder.id(name.to_pascal_case()))
-        } else {
+        if defs.is_empty() {
             None
+        } else {
+            Some(quote!(#(#defs)*))
+        }
+    }
+
+    /// Generate the type identifier of the values read or written, telling the two apart when
+    /// they are enumerated separately under the same name.
+    fn gen_type_ident(&self, usage: Usage) -> Option<Ident> {
+        let name = |enum_vals: &EnumeratedValues| {
+            let field_name = enum_vals.base_field.as_ref().unwrap_or(&self.name);
+            match enum_vals.name.as_ref() {
+                Some(enum_name) if enum_name != field_name => field_name.to_owned() + "_" + enum_name,
+                _ => field_name.to_owned(),
+            }
+        };
+
+        let enum_vals = self.enumerated_values(usage)?;
+        let mut enum_name = name(enum_vals);
+        match enum_vals.base_usage {
+            // The base field told its own values apart by their usage.
+            Some(Usage::Read) => enum_name += "_read",
+            Some(Usage::Write) => enum_name += "_write",
+            _ => {
+                if let (Some(read), Some(write)) = (self.enumerated_values(Usage::Read),
+                                                    self.enumerated_values(Usage::Write)) {
+                    if !ptr::eq(read, write) && name(read) == name(write) {
+                        enum_name += if usage == Usage::Read { "_read" } else { "_write" };
+                    }
+                }
+            }
+        }
+        Some(id(&enum_name.to_pascal_case()))
+    }
+
+    /// Enumerations without any values other than an `isDefault` one are left as the raw bits of
+    /// the field.
+    fn enumerated_values(&self, usage: Usage) -> Option<&EnumeratedValues> {
+        let enum_vals = match usage {
+            Usage::Read => self.read_enumerated_values(),
+            // Fields constrained to their enumerated values are written with them even when they
+            // are only enumerated for reading.
+            Usage::Write => self.write_enumerated_values().or_else(|| {
+                match self.write_constraint {
+                    Some(WriteConstraint::UseEnumeratedValues(true)) => {
+                        self.read_enumerated_values()
+                    }
+                    _ => None,
+                }
+            }),
+            Usage::ReadWrite => self.read_enumerated_values()
+                .filter(|&x| self.write_enumerated_values().is_some_and(|w| ptr::eq(x, w))),
+        };
+        enum_vals.filter(|x| x.values.iter().any(|v| v.value.is_some()))
+    }
+
+    /// The `isDefault` value stands for every value of the field that isn't enumerated, so it is
+    /// written as the first of those. A field whose values are all enumerated has no use for it.
+    fn default_value<'a>(&self, enum_vals: &'a EnumeratedValues)
+                         -> Option<(&'a EnumeratedValue, u64)> {
+        let default = enum_vals.values.iter().find(|x| x.is_default == Some(true))?;
+        let mut values: Vec<u64> = enum_vals.values.iter()
+            .filter_map(|x| x.value)
+            .filter(|&v| v <= self.mask())
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+
+        // The first position of the sorted values not holding its own index is the smallest gap.
+        let value = values.iter().zip(0u64..)
+            .find(|&(&v, i)| v != i)
+            .map_or(values.len() as u64, |(_, i)| i);
+        if value > self.mask() {
+            return None;
         }
+        Some((default, value))
     }
 
+    /// Generate the enum of a set of enumerated values of the field. The `isDefault` value, if
+    /// any, is the variant read for every value that isn't enumerated.
+    fn gen_enum(&self, register: &Register, enum_vals: &EnumeratedValues, name: &Ident)
+                -> TokenStream {
+        let mut values: Vec<(&EnumeratedValue, u64)> = enum_vals.values.iter()
+            .filter_map(|x| x.value.map(|v| (x, v)))
+            .collect();
+        values.extend(self.default_value(enum_vals));
+
+        let docs = values.iter().map(|x| doc(x.0.description.as_deref()));
+        let keys = values.iter().map(|x| id(&x.0.name.to_pascal_case()));
+        let vals = values.iter().map(|x| register.value_lit(x.1));
+        let value_ty = register.value_ty();
+        let field_doc = doc(self.description.as_deref());
+
+        quote! {
+            #field_doc
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[allow(dead_code, missing_docs)]
+            #[repr(#value_ty)]
+            pub enum #name {
+                #(#docs #keys = #vals),*
+            }
+        }
+    }
 
     /// Generate struct representation of register field update in the form of:
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// impl Cr {
     ///     #[inline(always)]
     ///     pub fn set_rx<'a>('a self, new_value: bool) -> CrUpdate<'a> {
@@ -245,112 +450,309 @@ impl GenField for Field {
     ///     }
     /// }
     /// ```
-    fn gen_update(&self, cx: &ExtCtxt, register: &Register) -> Vec<P<syntax::ast::Item>> {
-        let builder    = aster::AstBuilder::new();
-        let field_name = builder.id("set_".to_string() + &self.name.to_snake_case());
-        let field_ty   = self.gen_type();
+    ///
+    /// A field whose `writeConstraint` is a range instead has a setter returning the value as the
+    /// error when it is out of range, along with an unsafe `set_rx_unchecked` which isn't checked.
+    fn gen_update(&self, register: &Register) -> Vec<TokenStream> {
+        let field_name = id(&("set_".to_string() + &self.name.to_snake_case()));
+        let field_ty   = self.gen_type(Usage::Write);
         let bit_offset = self.bit_range.offset;
-        let bit_width  = self.bit_range.width;
+        let bit_mask   = register.value_lit(self.mask());
+        let value_ty   = register.value_ty();
+        let field_doc  = doc(self.description.as_deref());
 
         let reg_name_update = register.updater_name();
         let reg_type_name = register.type_name();
 
+        let set = quote! {
+            self.value = (self.value & !(#bit_mask << #bit_offset)) |
+                ((new_value as #value_ty) & #bit_mask) << #bit_offset;
+            self.mask |= #bit_mask << #bit_offset;
+            self
+        };
+
+        // Fields whose values are constrained to a range are checked before they are set, unless
+        // the unchecked setter is used. The range is limited to the values the field can hold.
+        let range = match self.write_constraint {
+            Some(WriteConstraint::Range { minimum, maximum })
+                if self.enumerated_values(Usage::Write).is_none() && self.bit_range.width > 1 =>
+            {
+                let maximum = u64::from(maximum).min(self.mask());
+                if u64::from(minimum) > maximum {
+                    panic!("writeConstraint range of field {} is outside of its {} bits",
+                           self.name, self.bit_range.width);
+                }
+                Some((Literal::u64_unsuffixed(minimum.into()), Literal::u64_unsuffixed(maximum)))
+            }
+            _ => None,
+        };
         let mut v = Vec::new();
-        v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl $reg_type_name {
-                            #[inline(always)]
-                            pub fn $field_name<'a>(&'a self, new_value: $field_ty) -> $reg_name_update<'a> {
-                                let mut setter: $reg_name_update = $reg_name_update::new(self);
-                                setter.$field_name(new_value);
-                                setter
+        if let Some((minimum, maximum)) = range {
+            let unchecked_name = id(&format!("set_{}_unchecked", self.name.to_snake_case()));
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        #field_doc
+                        ///
+                        /// The value is returned as the error when it is outside of the range
+                        /// the field may be written with.
+                        #[inline(always)]
+                        pub fn #field_name<'a>(&'a self, new_value: #field_ty)
+                            -> ::core::result::Result<#reg_name_update<'a>, #field_ty>
+                        {
+                            let mut setter: #reg_name_update = #reg_name_update::new(self);
+                            setter.#field_name(new_value)?;
+                            ::core::result::Result::Ok(setter)
+                        }
+
+                        #field_doc
+                        ///
+                        /// # Safety
+                        ///
+                        /// The value must be within the range the field may be written with.
+                        #[inline(always)]
+                        pub unsafe fn #unchecked_name<'a>(&'a self, new_value: #field_ty) -> #reg_name_update<'a> {
+                            let mut setter: #reg_name_update = #reg_name_update::new(self);
+                            setter.#unchecked_name(new_value);
+                            setter
+                        }
+                    }
+                });
+
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl<'a> #reg_name_update<'a> {
+                        #field_doc
+                        ///
+                        /// The value is returned as the error when it is outside of the range
+                        /// the field may be written with.
+                        #[inline(always)]
+                        pub fn #field_name<'b>(&'b mut self, new_value: #field_ty)
+                            -> ::core::result::Result<&'b mut #reg_name_update<'a>, #field_ty>
+                        {
+                            if !(#minimum..=#maximum).contains(&new_value) {
+                                return ::core::result::Result::Err(new_value);
                             }
+                            ::core::result::Result::Ok(unsafe { self.#unchecked_name(new_value) })
                         }
-                       ).unwrap());
 
+                        #field_doc
+                        ///
+                        /// # Safety
+                        ///
+                        /// The value must be within the range the field may be written with.
+                        #[inline(always)]
+                        pub unsafe fn #unchecked_name<'b>(&'b mut self, new_value: #field_ty) -> &'b mut #reg_name_update<'a> {
+                            #set
+                        }
+                    }
+                });
+        } else {
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name<'a>(&'a self, new_value: #field_ty) -> #reg_name_update<'a> {
+                            let mut setter: #reg_name_update = #reg_name_update::new(self);
+                            setter.#field_name(new_value);
+                            setter
+                        }
+                    }
+                });
+
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl<'a> #reg_name_update<'a> {
+                        #field_doc
+                        #[inline(always)]
+                        pub fn #field_name<'b>(&'b mut self, new_value: #field_ty) -> &'b mut #reg_name_update<'a> {
+                            #set
+                        }
+                    }
+                });
+        }
+        v
+    }
+
+    /// Generate struct representation of clearing a field that is cleared by writing one in the
+    /// form of:
+    ///
+    /// ```rust,ignore
+    /// impl Sr {
+    ///     #[inline(always)]
+    ///     pub fn clear_ovr<'a>(&'a self) -> SrUpdate<'a> {
+    ///         let mut setter: SrUpdate = SrUpdate::new(self);
+    ///         setter.clear_ovr();
+    ///         setter
+    ///     }
+    /// }
+    ///
+    /// impl<'a> SrUpdate<'a> {
+    ///     #[inline(always)]
+    ///     pub fn clear_ovr<'b>(&'b mut self) -> &'b mut SrUpdate<'a> {
+    ///         self.value |= 1 << 6;
+    ///         self.mask |= 1 << 6;
+    ///         self
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// A field cleared by writing zero instead has its bits cleared in the value written.
+    fn gen_clear(&self, register: &Register) -> Vec<TokenStream> {
+        let field_name = id(&("clear_".to_string() + &self.name.to_snake_case()));
+        let bit_offset = self.bit_range.offset;
+        let bit_mask   = register.value_lit(self.mask());
+        let field_doc  = doc(self.description.as_deref());
+
+        let clear = match self.modified_write_values(register) {
+            Some(ModifiedWriteValues::OneToClear) => quote!(self.value |= #bit_mask << #bit_offset),
+            Some(ModifiedWriteValues::ZeroToClear) => {
+                quote!(self.value &= !(#bit_mask << #bit_offset))
+            }
+            _ => return Vec::new(),
+        };
+
+        let reg_name_update = register.updater_name();
+        let reg_type_name = register.type_name();
+
+        let mut v = Vec::new();
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl<'a> $reg_name_update<'a> {
-                            #[inline(always)]
-                            pub fn $field_name<'b>(&'b mut self, new_value: $field_ty) -> &'b mut $reg_name_update<'a> {
-                                self.value = (self.value & !($bit_width << $bit_offset)) |
-                                    ((new_value as u32) & $bit_width) << $bit_offset;
-                                self.mask |= $bit_width << $bit_offset;
-                                self
-                            }
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl #reg_type_name {
+                    #field_doc
+                    #[inline(always)]
+                    pub fn #field_name<'a>(&'a self) -> #reg_name_update<'a> {
+                        let mut setter: #reg_name_update = #reg_name_update::new(self);
+                        setter.#field_name();
+                        setter
+                    }
+                }
+            });
+
+        v.push(
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl<'a> #reg_name_update<'a> {
+                    #field_doc
+                    #[inline(always)]
+                    pub fn #field_name<'b>(&'b mut self) -> &'b mut #reg_name_update<'a> {
+                        #clear;
+                        self.mask |= #bit_mask << #bit_offset;
+                        self
+                    }
+                }
+            });
         v
     }
+
+    fn modified_write_values(&self, register: &Register) -> Option<ModifiedWriteValues> {
+        self.modified_write_values.or(register.modified_write_values)
+    }
+
+    /// Compute the mask from the width of the field so that for example a 4 bit field is masked
+    /// with `0xf`.
+    fn mask(&self) -> u64 {
+        match self.bit_range.width {
+            0 => panic!("Unknown bit width"),
+            width if width >= 64 => !0,
+            width => (1 << width) - 1,
+        }
+    }
 }
 
 trait GenReg {
     /// Generate register memory map information (including fields).
-    fn gen_mmap(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>>;
+    fn gen_mmap(&self) -> Vec<TokenStream>;
 
     /// Generate register constants information.
-    fn gen_const(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>>;
+    fn gen_const(&self) -> Vec<TokenStream>;
 
     // Generate getter information.
-    fn gen_getter(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>>;
+    fn gen_getter(&self) -> Vec<TokenStream>;
 
     // Generate updater information.
-    fn gen_updater(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>>;
+    fn gen_updater(&self) -> Vec<TokenStream>;
+
+    /// Generate the closure based reads and writes.
+    fn gen_read_write(&self) -> Vec<TokenStream>;
 
     /// Generate getter name.
-    fn getter_name(&self) -> ast::Ident;
+    fn getter_name(&self) -> Ident;
 
     /// Generate type name.
-    fn type_name(&self) -> ast::Ident;
+    fn type_name(&self) -> Ident;
 
     /// Generate updater name.
-    fn updater_name(&self) -> ast::Ident;
+    fn updater_name(&self) -> Ident;
+
+    /// Generate the name of a method of the register, or of its Get and Update types, that
+    /// doesn't collide with the methods of its fields.
+    fn method_name(&self, name: &str) -> Ident;
 
+    /// Number of bytes occupied by the register.
+    fn byte_size(&self) -> u32;
+
+    /// Generate the type holding the register's value.
+    fn value_ty(&self) -> TokenStream;
+
+    /// Generate a literal of the type holding the register's value.
+    fn value_lit(&self, value: u64) -> Literal;
+
+    /// Masks of the bits left unmodified by writing zero to them and of those left unmodified by
+    /// writing one.
+    fn unmodified_masks(&self) -> (u64, u64);
+
+    /// Whether reading the register has side effects such as clearing it.
+    fn read_side_effects(&self) -> bool;
 }
 
 impl GenReg for Register {
 
     /// Generate all of the Rust code needed to interface to this regster.
-    fn gen_mmap(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>> {
+    fn gen_mmap(&self) -> Vec<TokenStream> {
         let mut v = Vec::new();
 
         // First we generate constant software associated with all registers.
-        v.append(&mut self.gen_const(&cx));
+        v.append(&mut self.gen_const());
 
         if self.access != Some(Access::WriteOnly) {
             // Now we generate Get specific software.
-            v.append(&mut self.gen_getter(&cx));
+            v.append(&mut self.gen_getter());
         }
 
         if self.access != Some(Access::ReadOnly) {
             // Now we generate Update specific software.
-            v.append(&mut self.gen_updater(&cx));
+            v.append(&mut self.gen_updater());
         }
 
+        // Along with the explicit reads and writes using them.
+        v.append(&mut self.gen_read_write());
+
         // Begin generating field information.
         if let Some(fields) = self.fields.as_ref() {
             // Generate the field's type definitions if necessary.
-            v.append(&mut fields.iter()
-                     .filter_map(|x| x.gen_type_def(&cx))
-                     .collect::<Vec<_>>());
+            v.extend(fields.iter().filter_map(|x| x.gen_type_def(self)));
 
             if self.access != Some(Access::WriteOnly) {
                 // For each of the register's fields we generate the field's getter.
-                v.append(&mut
-                         fields.iter()
+                v.extend(fields.iter()
                          .filter(|x| x.access != Some(Access::WriteOnly))
-                         .flat_map(|x| x.gen_get(&cx, self))
-                         .collect::<Vec<_>>());
+                         .flat_map(|x| x.gen_get(self)));
             }
 
             if self.access != Some(Access::ReadOnly) {
                 // and updater.
-                v.append(&mut
-                         fields.iter()
+                // Fields which may only be written as they were read are left as they are.
+                v.extend(fields.iter()
                          .filter(|x| x.access != Some(Access::ReadOnly))
-                         .flat_map(|x| x.gen_update(&cx, self))
-                         .collect::<Vec<_>>());
+                         .filter(|x| x.write_constraint != Some(WriteConstraint::WriteAsRead(true)))
+                         .flat_map(|x| x.gen_update(self).into_iter().chain(x.gen_clear(self))));
             }
         }
         v
@@ -358,27 +760,53 @@ impl GenReg for Register {
 
     /// Generate all of the constant register details.
     ///
-    /// The result should look like:
+    /// The result should look like the following where the width of the value follows the size of
+    /// the register:
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// #[allow(dead_code), missing_docs)]
     /// #[repr(C)]
     /// pub struct Cr {
     ///     value: VolatileCell<u32>,
     /// }
+    ///
+    /// #[allow(dead_code, missing_docs)]
+    /// impl Cr {
+    ///     /// Value of the register following reset.
+    ///     pub const RESET_VALUE: u32 = 0u32;
+    ///     /// Bits of the register with a defined value following reset.
+    ///     pub const RESET_MASK: u32 = 4294967295u32;
+    /// }
     /// ```
-    fn gen_const(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>> {
+    fn gen_const(&self) -> Vec<TokenStream> {
         let mut v = Vec::new();
 
         let reg_type_name = self.type_name();
+        let value_ty = self.value_ty();
+        let reg_doc = doc(Some(&self.description));
+        let reset_value = self.value_lit(self.reset_value.unwrap_or(0));
+        let reset_mask = self.value_lit(self.reset_mask.unwrap_or(!0));
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        #[repr(C)]
-                        pub struct $reg_type_name {
-                            value: VolatileCell<u32>,
-                        }).unwrap());
+            quote! {
+                #reg_doc
+                #[allow(dead_code, missing_docs)]
+                #[repr(C)]
+                pub struct #reg_type_name {
+                    value: VolatileCell<#value_ty>,
+                }
+            });
+
+        v.push(
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl #reg_type_name {
+                    /// Value of the register following reset.
+                    pub const RESET_VALUE: #value_ty = #reset_value;
+                    /// Bits of the register with a defined value following reset.
+                    pub const RESET_MASK: #value_ty = #reset_mask;
+                }
+            });
 
         v
     }
@@ -387,7 +815,7 @@ impl GenReg for Register {
     ///
     /// The result should look like:
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// #[allow(dead_code), missing_docs)]
     /// impl Cr {
     ///     #[inline(always)]
@@ -409,40 +837,121 @@ impl GenReg for Register {
     ///     pub fn new(reg: Cr) -> CrGet {
     ///         CrGet { value: reg.value.get() }
     ///     }
+    ///
+    ///     #[inline(always)]
+    ///     pub fn bits(&self) -> u32 {
+    ///         self.value
+    ///     }
+    /// }
+    ///
+    /// #[allow(dead_code), missing_docs)]
+    /// impl ::core::fmt::Debug for CrGet {
+    ///     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+    ///         let mut s = f.debug_struct("CrGet");
+    ///         s.field("rx", &self.rx());
+    ///         s.finish()
+    ///     }
     /// }
     /// ```
-    fn gen_getter(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>> {
+    fn gen_getter(&self) -> Vec<TokenStream> {
         let mut v = Vec::new();
         let reg_type_name = self.type_name();
         let reg_name_get = self.getter_name();
+        let value_ty = self.value_ty();
+        let new = self.method_name("new");
+        let bits = self.method_name("bits");
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        #[derive(Clone)]
-                        pub struct $reg_name_get {
-                            value: u32,
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                #[derive(Clone)]
+                pub struct #reg_name_get {
+                    value: #value_ty,
+                }
+            });
 
+        // Reads with side effects are made through a method saying so.
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl $reg_type_name {
-                            #[inline(always)]
-                            pub fn get(&self) -> $reg_name_get {
-                                $reg_name_get::new(self)
-                            }
-                        }).unwrap());
+            if self.read_side_effects() {
+                let read_with_side_effects = self.method_name("read_with_side_effects");
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        /// Read the register, which has side effects such as clearing it.
+                        #[inline(always)]
+                        pub fn #read_with_side_effects(&self) -> #reg_name_get {
+                            #reg_name_get::#new(self)
+                        }
+                    }
+                }
+            } else {
+                let get = self.method_name("get");
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        #[inline(always)]
+                        pub fn #get(&self) -> #reg_name_get {
+                            #reg_name_get::#new(self)
+                        }
+                    }
+                }
+            });
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl $reg_name_get {
-                            #[inline(always)]
-                            pub fn new(reg: &$reg_type_name) -> $reg_name_get {
-                                $reg_name_get { value: reg.value.get() }
-                            }
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl #reg_name_get {
+                    #[inline(always)]
+                    pub fn #new(reg: &#reg_type_name) -> #reg_name_get {
+                        #reg_name_get { value: reg.value.get() }
+                    }
+
+                    /// The raw value read from the register.
+                    #[inline(always)]
+                    pub fn #bits(&self) -> #value_ty {
+                        self.value
+                    }
+                }
+            });
+
+        // Snapshots are printed as their readable fields, with enumerated values that aren't
+        // enumerated printed as their bits.
+        let name = reg_name_get.to_string();
+        let fields = self.fields.iter().flatten()
+            .filter(|x| x.access != Some(Access::WriteOnly))
+            .map(|x| {
+                let field_name = x.name.to_snake_case();
+                let getter = id(&field_name);
+                if x.enumerated_values(Usage::Read).is_some() {
+                    let bits = id(&(field_name.clone() + "_bits"));
+                    quote! {
+                        match self.#getter() {
+                            ::core::option::Option::Some(ref value) => s.field(#field_name, value),
+                            ::core::option::Option::None => s.field(#field_name, &self.#bits()),
+                        };
+                    }
+                } else {
+                    quote!(s.field(#field_name, &self.#getter());)
+                }
+            })
+            .collect::<Vec<_>>();
+        let fields = if fields.is_empty() {
+            vec![quote!(s.field("bits", &self.value);)]
+        } else {
+            fields
+        };
+
+        v.push(
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl ::core::fmt::Debug for #reg_name_get {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        let mut s = f.debug_struct(#name);
+                        #(#fields)*
+                        s.finish()
+                    }
+                }
+            });
         v
     }
 
@@ -450,13 +959,18 @@ impl GenReg for Register {
     ///
     /// The result should look like:
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// #[allow(dead_code), missing_docs)]
     /// impl Cr {
     ///     #[inline(always)]
     ///     pub fn ignoring_state(&self) -> CrUpdate {
     ///         CrUpdate::new_ignoring_state(self)
     ///     }
+    ///
+    ///     #[inline(always)]
+    ///     pub fn reset(&self) {
+    ///         self.value.set(Cr::RESET_VALUE)
+    ///     }
     /// }
     ///
     /// #[allow(dead_code), missing_docs)]
@@ -467,17 +981,12 @@ impl GenReg for Register {
     ///     reg: &'a Cr,
     /// }
     ///
-    /// TODO is the clear mask correct?
     /// #[allow(dead_code), missing_docs)]
     /// impl<'a> Drop for CrUpdate<'a> {
     ///     #[inline(always)]
     ///     fn drop(&mut self) {
-    ///         let clear_mask: u32 = 1u32 as u32;
     ///         if self.mask != 0 {
-    ///             let v: u32 =
-    ///                 if self.write_only { 0 } else { self.reg.value.get() } &
-    ///                     !clear_mask & !self.mask;
-    ///             self.reg.value.set(self.value | v);
+    ///             self.commit();
     ///         }
     ///     }
     /// }
@@ -490,394 +999,1484 @@ impl GenReg for Register {
     ///     }
     ///     #[inline(always)]
     ///     pub fn new_ignoring_state(reg: &'a Cr) -> CrUpdate<'a> {
-    ///         CrUpdate { value: 0, mask: 0, write_only: true, reg: reg }
+    ///         CrUpdate { value: Cr::RESET_VALUE, mask: 0, write_only: true, reg: reg }
+    ///     }
+    ///     #[inline(always)]
+    ///     fn commit(&mut self) {
+    ///         let zero_mask: u32 = 0u32;
+    ///         let one_mask: u32 = 0u32;
+    ///         let v: u32 = if self.write_only { self.value } else { self.reg.value.get() };
+    ///         self.reg.value.set((self.value & self.mask) |
+    ///                            ((v & !zero_mask) | one_mask) & !self.mask);
+    ///         self.mask = 0;
     ///     }
     /// }
     /// ```
-    fn gen_updater(&self, cx: &ExtCtxt) -> Vec<P<syntax::ast::Item>> {
+    ///
+    /// The bits of fields that aren't updated are written back as they are, except for those
+    /// with `modifiedWriteValues` such as `oneToClear`, which are written as the value leaving
+    /// them unmodified.
+    ///
+    /// Registers with a `readAction` are never read to be updated, so `new` starts from their
+    /// reset value as `new_ignoring_state` does. The methods of the register are renamed by
+    /// `method_name` when a field has a method of the same name.
+    fn gen_updater(&self) -> Vec<TokenStream> {
         let mut v = Vec::new();
         let reg_type_name = self.type_name();
         let reg_name_update = self.updater_name();
+        let value_ty = self.value_ty();
+        let (zero_mask, one_mask) = self.unmodified_masks();
+        let zero_mask = self.value_lit(zero_mask);
+        let one_mask = self.value_lit(one_mask);
+        let ignoring_state = self.method_name("ignoring_state");
+        let reset = self.method_name("reset");
+        let bits = self.method_name("bits");
+        let set_bits = self.method_name("set_bits");
+
+        // Registers whose reads have side effects are never read to be updated and are instead
+        // written starting from their reset value.
+        let new = if self.read_side_effects() {
+            quote!(#reg_name_update::new_ignoring_state(reg))
+        } else {
+            quote!(#reg_name_update {value: 0, mask: 0, write_only: false, reg: reg})
+        };
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        pub struct $reg_name_update<'a> {
-                            value: u32,
-                            mask: u32,
-                            write_only: bool,
-                            reg: &'a $reg_type_name,
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                pub struct #reg_name_update<'a> {
+                    value: #value_ty,
+                    mask: #value_ty,
+                    write_only: bool,
+                    reg: &'a #reg_type_name,
+                }
+            });
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl $reg_type_name {
-                            #[inline(always)]
-                            pub fn ignoring_state(&self) -> $reg_name_update {
-                                $reg_name_update::new_ignoring_state(self)
-                            }
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl #reg_type_name {
+                    /// Update the register starting from its reset value rather than its current
+                    /// state.
+                    #[inline(always)]
+                    pub fn #ignoring_state(&self) -> #reg_name_update {
+                        #reg_name_update::new_ignoring_state(self)
+                    }
+
+                    /// Write the reset value to the register.
+                    #[inline(always)]
+                    pub fn #reset(&self) {
+                        self.value.set(#reg_type_name::RESET_VALUE)
+                    }
+                }
+            });
 
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl<'a> Drop for $reg_name_update<'a> {
-                            #[inline(always)]
-                            fn drop(&mut self) {
-                                let clear_mask: u32 = 1u32 as u32;
-                                if self.mask != 0 {
-                                    let v: u32 =
-                                        if self.write_only { 0 } else { self.reg.value.get() } &
-                                            !clear_mask & !self.mask;
-                                    self.reg.value.set(self.value | v);
-                                }
-                            }
-                        }).unwrap());
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl<'a> Drop for #reg_name_update<'a> {
+                    #[inline(always)]
+                    fn drop(&mut self) {
+                        if self.mask != 0 {
+                            self.commit();
+                        }
+                    }
+                }
+            });
 
         v.push(
-            quote_item!(&cx,
-                        #[allow(dead_code, missing_docs)]
-                        impl<'a> $reg_name_update<'a> {
-                            #[inline(always)]
-                            pub fn new(reg: &'a $reg_type_name) -> $reg_name_update<'a> {
-                                $reg_name_update {value: 0, mask: 0, write_only: false, reg: reg}
-                            }
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl<'a> #reg_name_update<'a> {
+                    #[inline(always)]
+                    pub fn new(reg: &'a #reg_type_name) -> #reg_name_update<'a> {
+                        #new
+                    }
+
+                    #[inline(always)]
+                    pub fn new_ignoring_state(reg: &'a #reg_type_name) -> #reg_name_update<'a> {
+                        #reg_name_update {
+                            value: #reg_type_name::RESET_VALUE,
+                            mask: 0,
+                            write_only: true,
+                            reg: reg,
+                        }
+                    }
+
+                    /// The raw value to be written to the register.
+                    #[inline(always)]
+                    pub fn #bits(&self) -> #value_ty {
+                        self.value
+                    }
+
+                    /// Set the raw value written to the whole register.
+                    ///
+                    /// # Safety
+                    ///
+                    /// The value bypasses the fields of the register, so any of its bits may be
+                    /// written with values the fields don't allow.
+                    #[inline(always)]
+                    pub unsafe fn #set_bits(&mut self, value: #value_ty) -> &mut #reg_name_update<'a> {
+                        self.value = value;
+                        self.mask = !0;
+                        self
+                    }
+
+                    /// Write the value to the register, leaving nothing to write when dropped.
+                    #[inline(always)]
+                    fn commit(&mut self) {
+                        let zero_mask: #value_ty = #zero_mask;
+                        let one_mask: #value_ty = #one_mask;
+                        let v: #value_ty =
+                            if self.write_only { self.value } else { self.reg.value.get() };
+                        self.reg.value.set((self.value & self.mask) |
+                                           ((v & !zero_mask) | one_mask) & !self.mask);
+                        self.mask = 0;
+                    }
+                }
+            });
+        v
+    }
 
-                            #[inline(always)]
-                            pub fn new_ignoring_state(reg: &'a $reg_type_name) -> $reg_name_update<'a> {
-                                $reg_name_update {value: 0, mask: 0, write_only: true, reg: reg}
-                            }
-                        }).unwrap());
+    /// Generate the reads and writes of the register made at the call in the form of:
+    ///
+    /// ```rust,ignore
+    /// #[allow(dead_code, missing_docs)]
+    /// impl Cr {
+    ///     #[inline(always)]
+    ///     pub fn read(&self) -> CrGet {
+    ///         CrGet::new(self)
+    ///     }
+    ///
+    ///     #[inline(always)]
+    ///     pub fn write<'a, F>(&'a self, f: F)
+    ///         where F: for<'w> FnOnce(&'w mut CrUpdate<'a>) -> &'w mut CrUpdate<'a>
+    ///     {
+    ///         let mut w = CrUpdate::new_ignoring_state(self);
+    ///         f(&mut w);
+    ///         w.commit();
+    ///     }
+    ///
+    ///     #[inline(always)]
+    ///     pub fn modify<'a, F>(&'a self, f: F)
+    ///         where F: for<'r, 'w> FnOnce(&'r CrGet, &'w mut CrUpdate<'a>) -> &'w mut CrUpdate<'a>
+    ///     {
+    ///         let r = CrGet::new(self);
+    ///         let mut w = CrUpdate { value: r.value, mask: 0, write_only: true, reg: self };
+    ///         f(&r, &mut w);
+    ///         w.commit();
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Registers whose reads have side effects have neither `read` nor `modify`. A field with a
+    /// method of the same name, such as a `READ` field, renames the register's as `read_`.
+    fn gen_read_write(&self) -> Vec<TokenStream> {
+        let mut v = Vec::new();
+        let reg_type_name = self.type_name();
+        let reg_name_get = self.getter_name();
+        let reg_name_update = self.updater_name();
+        let value_ty = self.value_ty();
+        let new = self.method_name("new");
+        let read = self.method_name("read");
+        let read_bits = self.method_name("read_bits");
+        let write = self.method_name("write");
+        let write_bits = self.method_name("write_bits");
+        let modify = self.method_name("modify");
+
+        let readable = self.access != Some(Access::WriteOnly) && !self.read_side_effects();
+        let writable = self.access != Some(Access::ReadOnly);
+
+        if readable {
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        /// Read the register.
+                        #[inline(always)]
+                        pub fn #read(&self) -> #reg_name_get {
+                            #reg_name_get::#new(self)
+                        }
+
+                        /// Read the raw value of the register.
+                        #[inline(always)]
+                        pub fn #read_bits(&self) -> #value_ty {
+                            self.value.get()
+                        }
+                    }
+                });
+        }
+
+        if writable {
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        /// Write the register with the fields set by `f`, and the others at their
+                        /// reset value.
+                        #[inline(always)]
+                        pub fn #write<'a, F>(&'a self, f: F)
+                            where F: for<'w> FnOnce(&'w mut #reg_name_update<'a>)
+                                                 -> &'w mut #reg_name_update<'a>
+                        {
+                            let mut w = #reg_name_update::new_ignoring_state(self);
+                            f(&mut w);
+                            w.commit();
+                        }
+
+                        /// Write the raw value to the register.
+                        ///
+                        /// # Safety
+                        ///
+                        /// The value bypasses the fields of the register, so any of its bits may
+                        /// be written with values the fields don't allow.
+                        #[inline(always)]
+                        pub unsafe fn #write_bits(&self, value: #value_ty) {
+                            self.value.set(value)
+                        }
+                    }
+                });
+        }
+
+        if readable && writable {
+            v.push(
+                quote! {
+                    #[allow(dead_code, missing_docs)]
+                    impl #reg_type_name {
+                        /// Read the register once and write it back with the fields set by `f`
+                        /// from what was read.
+                        #[inline(always)]
+                        pub fn #modify<'a, F>(&'a self, f: F)
+                            where F: for<'r, 'w> FnOnce(&'r #reg_name_get,
+                                                        &'w mut #reg_name_update<'a>)
+                                                        -> &'w mut #reg_name_update<'a>
+                        {
+                            let r = #reg_name_get::#new(self);
+                            let mut w = #reg_name_update {
+                                value: r.value,
+                                mask: 0,
+                                write_only: true,
+                                reg: self,
+                            };
+                            f(&r, &mut w);
+                            w.commit();
+                        }
+                    }
+                });
+        }
         v
     }
 
     /// Generate getter name.
-    fn getter_name(&self) -> ast::Ident {
-        let builder = aster::AstBuilder::new();
+    fn getter_name(&self) -> Ident {
         let name = self.name.to_pascal_case();
-        builder.id(name.to_owned() + "Get")
+        id(&(name + "Get"))
     }
 
     /// Generate type name.
-    fn type_name(&self) -> ast::Ident {
-        let builder = aster::AstBuilder::new();
+    fn type_name(&self) -> Ident {
         let name = self.name.to_pascal_case();
-        builder.id(name.to_owned())
+        id(&name)
     }
 
     /// Generate updater name.
-    fn updater_name(&self) -> ast::Ident {
-        let builder = aster::AstBuilder::new();
+    fn updater_name(&self) -> Ident {
         let name = self.name.to_pascal_case();
-        builder.id(name.to_owned() + "Update")
+        id(&(name + "Update"))
+    }
+
+    /// Generate the name of a method, suffixed with underscores for as long as a field has a
+    /// method of that name. So a register with a `RESET` field has its `reset` method generated as
+    /// `reset_`.
+    fn method_name(&self, name: &str) -> Ident {
+        let mut field_methods = BTreeSet::new();
+        for field in self.fields.iter().flatten() {
+            let name = field.name.to_snake_case();
+            if field.enumerated_values(Usage::Read).is_some() {
+                field_methods.insert(name.clone() + "_bits");
+            }
+            if let Some(WriteConstraint::Range { .. }) = field.write_constraint {
+                field_methods.insert(format!("set_{}_unchecked", name));
+            }
+            if let Some(ModifiedWriteValues::OneToClear) |
+                   Some(ModifiedWriteValues::ZeroToClear) = field.modified_write_values(self) {
+                field_methods.insert("clear_".to_owned() + &name);
+            }
+            field_methods.insert("set_".to_owned() + &name);
+            field_methods.insert(name);
+        }
+
+        let mut name = name.to_owned();
+        while field_methods.contains(&name) {
+            name.push('_');
+        }
+        id(&name)
+    }
+
+    /// Number of bytes occupied by the register, defaulting to 32 bits.
+    fn byte_size(&self) -> u32 {
+        self.size.unwrap_or(32) / 8
+    }
+
+    /// Generate the unsigned type matching the register's size, defaulting to 32 bits.
+    fn value_ty(&self) -> TokenStream {
+        match self.size.unwrap_or(32) {
+            8 => quote!(u8),
+            16 => quote!(u16),
+            32 => quote!(u32),
+            64 => quote!(u64),
+            size => panic!("Unsupported register size {}", size),
+        }
+    }
+
+    /// Generate a literal of the type holding the register's value.
+    fn value_lit(&self, value: u64) -> Literal {
+        match self.size.unwrap_or(32) {
+            8 => Literal::u8_suffixed(value as u8),
+            16 => Literal::u16_suffixed(value as u16),
+            32 => Literal::u32_suffixed(value as u32),
+            64 => Literal::u64_suffixed(value),
+            size => panic!("Unsupported register size {}", size),
+        }
+    }
+
+    /// Masks of the bits left unmodified by writing zero to them and of those left unmodified by
+    /// writing one, taken from the `modifiedWriteValues` of the fields or else of the register.
+    fn unmodified_masks(&self) -> (u64, u64) {
+        let unmodified = |mwv| match mwv {
+            Some(ModifiedWriteValues::OneToClear) |
+            Some(ModifiedWriteValues::OneToSet) |
+            Some(ModifiedWriteValues::OneToToggle) => (!0, 0),
+            Some(ModifiedWriteValues::ZeroToClear) |
+            Some(ModifiedWriteValues::ZeroToSet) |
+            Some(ModifiedWriteValues::ZeroToToggle) => (0, !0),
+            _ => (0, 0),
+        };
+
+        match self.fields {
+            Some(ref fields) => fields.iter().fold((0, 0), |(zero, one), field| {
+                let (z, o) = unmodified(field.modified_write_values(self));
+                let mask = field.mask() << field.bit_range.offset;
+                (zero | (z & mask), one | (o & mask))
+            }),
+            None => unmodified(self.modified_write_values),
+        }
+    }
+
+    /// Whether the register or any of its fields has a `readAction`.
+    fn read_side_effects(&self) -> bool {
+        self.read_action.is_some() ||
+            self.fields.iter().flatten().any(|x| x.read_action.is_some())
+    }
+}
+
+/// Merge the registers and clusters of the peripheral `periph` is derived from beneath its own,
+/// which override those of the same name.
+///
+/// The result remains derived from the peripheral whose type it shares when the merged registers
+/// are identical to those of its base. Otherwise it is no longer derived and is generated as a
+/// peripheral in its own right.
+fn derive_periph(device: &Device, periph: &Peripheral) -> Peripheral {
+    let base_name = match periph.derived_from {
+        Some(ref base_name) => base_name,
+        None => return periph.clone(),
+    };
+    let base = device.peripherals.iter()
+        .find(|p| &p.name == base_name)
+        .unwrap_or_else(|| panic!("{} is derived from unknown peripheral {}",
+                                  periph.name, base_name));
+    let base = derive_periph(device, base);
+
+    let mut registers = base.registers.clone().unwrap_or_default();
+    for reg in periph.registers.iter().flatten() {
+        match registers.iter_mut().find(|r| r.name == reg.name) {
+            Some(r) => *r = reg.clone(),
+            None => registers.push(reg.clone()),
+        }
+    }
+    let mut clusters = base.clusters.clone();
+    for cluster in &periph.clusters {
+        match clusters.iter_mut().find(|c| c.name == cluster.name) {
+            Some(c) => *c = cluster.clone(),
+            None => clusters.push(cluster.clone()),
+        }
+    }
+
+    let registers = if base.registers.is_none() && registers.is_empty() {
+        None
+    } else {
+        Some(registers)
+    };
+    let derived_from = if registers == base.registers && clusters == base.clusters {
+        Some(base.derived_from.clone().unwrap_or(base.name.clone()))
+    } else {
+        None
+    };
+
+    Peripheral {
+        name: periph.name.clone(),
+        group_name: periph.group_name.clone().or(base.group_name),
+        description: periph.description.clone().or(base.description),
+        base_address: periph.base_address,
+        interrupt: periph.interrupt.clone(),
+        registers,
+        clusters,
+        derived_from,
     }
 }
 
 /// Generate complete memory mapped hardware definition in Rust for device.
-pub fn gen_device(cx: &mut ExtCtxt, device: &Device) -> Vec<P<syntax::ast::Item>> {
-    let builder = aster::AstBuilder::new();
+pub fn gen_device(device: &Device, options: &Options) -> Vec<TokenStream> {
+    // Resolve derived peripherals against the peripheral they are derived from.
+    let peripherals: Vec<Peripheral> = device.peripherals.iter()
+        .map(|p| derive_periph(device, p))
+        .collect();
 
     // First find all peripherals that have other peripherals derived from them.
     let mut derived_from: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
-    for ref periph in device.peripherals.iter() {
+    for periph in peripherals.iter() {
         // Iterate through the peripherals and add each derived_from name to the map of name to a
         // set of dependent peripherals.
         if let Some(derived_name) = periph.derived_from.as_ref() {
             derived_from.insert(derived_name, BTreeSet::new());
         }
     }
-    for ref periph in device.peripherals.iter() {
-        // Iterate through the peripherals and assign the peripheral that is derived from to each
-        // set.
-        if let Some(derived_name) = periph.derived_from.as_ref() {
-            let set = derived_from.get_mut::<&str>(&derived_name.as_str()).unwrap();
-            set.insert(&periph.name);
+    for periph in peripherals.iter() {
+        // Iterate through the peripherals and assign the peripheral that is derived from to each
+        // set.
+        if let Some(derived_name) = periph.derived_from.as_ref() {
+            let set = derived_from.get_mut(derived_name.as_str()).unwrap();
+            set.insert(&periph.name);
+        }
+    }
+
+    // Set of module names already defined.
+    let mut module_name_set: BTreeSet<&str> = BTreeSet::new();
+
+    let mut peripheral_items = Vec::new();
+    for periph in peripherals.iter() {
+
+        if periph.derived_from.is_none() {
+            let periph_items = gen_periph(periph);
+
+            // Wrap the peripheral items in a module.
+            let name = periph.name.as_str();
+            let group_name = periph.group_name.as_ref();
+
+            let periph_name = match group_name {
+                Some(group_name) if derived_from.contains_key(name) &&
+                    !module_name_set.contains(group_name.as_str()) => group_name,
+                _ => name,
+            };
+            let periph_mod_name = id(&periph_name.to_snake_case());
+            module_name_set.insert(periph_name);
+
+            // Build the variables that represent access to the hardware, for this peripheral and
+            // those derived from it.
+            let periph_ty = id(
+                &periph.group_name.as_deref().unwrap_or(periph_name).to_pascal_case());
+            let mut statics = vec![gen_periph_access(device, periph, &periph_ty, options)];
+            if let Some(set) = derived_from.get(periph.name.as_str()) {
+                for periph_name in set {
+                    let derived = peripherals.iter().find(|p| p.name == *periph_name).unwrap();
+                    statics.push(gen_periph_access(device, derived, &periph_ty, options));
+                }
+            }
+
+            let periph_item = quote! {
+                pub mod #periph_mod_name {
+                    use volatile_cell::VolatileCell;
+                    use core::ops::Drop;
+
+                    #(#periph_items)*
+                    #(#statics)*
+                }
+            };
+
+            peripheral_items.push(periph_item);
+        }
+    }
+
+    let mut interrupt_items = gen_interrupts(device);
+    if options.vector_table {
+        interrupt_items.append(&mut gen_vector_table(device));
+    }
+
+    // Create module housing the hardware.
+    let dev_name = id(&device.name.to_snake_case());
+    let dev_item = quote! {
+        pub mod #dev_name {
+            #(#interrupt_items)*
+            #(#peripheral_items)*
+        }
+    };
+
+    vec![dev_item]
+}
+
+/// Generate the access to a peripheral of the given type. By default it is an `extern` static
+/// linked against the symbol of the peripheral:
+///
+/// ```rust,ignore
+/// #[allow(dead_code)]
+/// extern {
+///     #[link_name = "mmap_stm32l4x6_spi1"]
+///     pub static SPI1: Spi1;
+/// }
+/// ```
+///
+/// With constant addresses it is instead a unit struct dereferencing to its registers at the
+/// peripheral's base address:
+///
+/// ```rust,ignore
+/// #[allow(dead_code, non_camel_case_types)]
+/// pub struct SPI1;
+///
+/// #[allow(dead_code)]
+/// impl SPI1 {
+///     pub const PTR: *const Spi1 = 0x4001_3000 as *const _;
+///
+///     #[inline(always)]
+///     pub fn regs() -> &'static Spi1 {
+///         unsafe { &*SPI1::PTR }
+///     }
+/// }
+///
+/// impl ::core::ops::Deref for SPI1 {
+///     type Target = Spi1;
+///
+///     #[inline(always)]
+///     fn deref(&self) -> &Spi1 {
+///         SPI1::regs()
+///     }
+/// }
+/// ```
+fn gen_periph_access(device: &Device, periph: &Peripheral, periph_ty: &Ident, options: &Options)
+                     -> TokenStream {
+    let periph_name = id(&periph.name.to_constant_case());
+    let periph_doc = doc(periph.description.as_deref());
+
+    if !options.const_addresses {
+        let link_name = link_symbol(device, &periph.name);
+        return quote! {
+            #[allow(dead_code)]
+            extern {
+                #periph_doc
+                #[link_name = #link_name]
+                pub static #periph_name: #periph_ty;
+            }
+        };
+    }
+
+    let address = periph.base_address;
+    let address = syn::LitInt::new(&format!("0x{:04x}_{:04x}", address >> 16, address & 0xffff),
+                                   Span::call_site());
+    quote! {
+        #periph_doc
+        #[allow(dead_code, non_camel_case_types)]
+        pub struct #periph_name;
+
+        #[allow(dead_code)]
+        impl #periph_name {
+            /// Pointer to the registers at the peripheral's base address.
+            pub const PTR: *const #periph_ty = #address as *const _;
+
+            /// The registers of the peripheral.
+            #[inline(always)]
+            pub fn regs() -> &'static #periph_ty {
+                unsafe { &*#periph_name::PTR }
+            }
+        }
+
+        impl ::core::ops::Deref for #periph_name {
+            type Target = #periph_ty;
+
+            #[inline(always)]
+            fn deref(&self) -> &#periph_ty {
+                #periph_name::regs()
+            }
+        }
+    }
+}
+
+/// The interrupts of every peripheral ordered by number, an interrupt shared by peripherals taken
+/// only once.
+fn interrupts(device: &Device) -> Vec<&Interrupt> {
+    let mut by_value: BTreeMap<u32, &Interrupt> = BTreeMap::new();
+    let mut by_name: BTreeMap<&str, u32> = BTreeMap::new();
+    for interrupt in device.peripherals.iter().flat_map(|p| p.interrupt.iter()) {
+        if let Some(other) = by_value.get(&interrupt.value) {
+            if other.name != interrupt.name {
+                panic!("interrupts {} and {} share the number {}",
+                       other.name, interrupt.name, interrupt.value);
+            }
+        }
+        if let Some(&value) = by_name.get(interrupt.name.as_str()) {
+            if value != interrupt.value {
+                panic!("interrupt {} has both the numbers {} and {}",
+                       interrupt.name, value, interrupt.value);
+            }
+        }
+        by_value.insert(interrupt.value, interrupt);
+        by_name.insert(&interrupt.name, interrupt.value);
+    }
+    by_value.into_values().collect()
+}
+
+/// Generate the enumeration of the device's interrupts in the form of:
+///
+/// ```rust,ignore
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// #[allow(dead_code, missing_docs)]
+/// #[repr(u16)]
+/// pub enum Interrupt {
+///     Spi1 = 35,
+/// }
+///
+/// impl Interrupt {
+///     #[inline(always)]
+///     pub fn nr(self) -> u16 {
+///         self as u16
+///     }
+/// }
+///
+/// impl ::core::convert::TryFrom<u16> for Interrupt {
+///     type Error = u16;
+///
+///     fn try_from(nr: u16) -> ::core::result::Result<Interrupt, u16> {
+///         match nr {
+///             35 => ::core::result::Result::Ok(Interrupt::Spi1),
+///             _ => ::core::result::Result::Err(nr),
+///         }
+///     }
+/// }
+/// ```
+fn gen_interrupts(device: &Device) -> Vec<TokenStream> {
+    let interrupts = interrupts(device);
+    if interrupts.is_empty() {
+        return Vec::new();
+    }
+
+    let docs = interrupts.iter().map(|i| doc(i.description.as_deref()));
+    let names: Vec<Ident> = interrupts.iter().map(|i| id(&i.name.to_pascal_case())).collect();
+    let values: Vec<Literal> = interrupts.iter()
+        .map(|i| {
+            let value = u16::try_from(i.value).unwrap_or_else(|_| {
+                panic!("interrupt {} number {} doesn't fit in 16 bits", i.name, i.value)
+            });
+            Literal::u16_unsuffixed(value)
+        })
+        .collect();
+
+    vec![
+        quote! {
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[allow(dead_code, missing_docs)]
+            #[repr(u16)]
+            pub enum Interrupt {
+                #(#docs #names = #values),*
+            }
+
+            #[allow(dead_code, missing_docs)]
+            impl Interrupt {
+                #[inline(always)]
+                pub fn nr(self) -> u16 {
+                    self as u16
+                }
+            }
+
+            impl ::core::convert::TryFrom<u16> for Interrupt {
+                type Error = u16;
+
+                fn try_from(nr: u16) -> ::core::result::Result<Interrupt, u16> {
+                    match nr {
+                        #(#values => ::core::result::Result::Ok(Interrupt::#names),)*
+                        _ => ::core::result::Result::Err(nr),
+                    }
+                }
+            }
+        }
+    ]
+}
+
+/// Generate the Cortex-M vector table holding the handler of each of the device's interrupts in
+/// the form of:
+///
+/// ```rust,ignore
+/// #[allow(dead_code, non_snake_case)]
+/// extern "C" {
+///     fn WWDG();
+///     fn EXTI0();
+/// }
+///
+/// #[doc(hidden)]
+/// #[allow(dead_code)]
+/// pub union Vector {
+///     handler: unsafe extern "C" fn(),
+///     reserved: usize,
+/// }
+///
+/// #[doc(hidden)]
+/// #[link_section = ".vector_table.interrupts"]
+/// #[no_mangle]
+/// pub static __INTERRUPTS: [Vector; 7] = [
+///     Vector { handler: WWDG },
+///     Vector { reserved: 0 },
+///     ...
+///     Vector { handler: EXTI0 },
+/// ];
+/// ```
+///
+/// Handlers the application doesn't define are provided by `DefaultHandler` through the linker
+/// script of `gen_link_mem`.
+fn gen_vector_table(device: &Device) -> Vec<TokenStream> {
+    let interrupts = interrupts(device);
+    let len = match interrupts.last() {
+        Some(interrupt) => interrupt.value as usize + 1,
+        None => return Vec::new(),
+    };
+
+    let handlers: Vec<Ident> = interrupts.iter().map(|i| id(&i.name)).collect();
+    let mut vectors = vec![quote!(Vector { reserved: 0 }); len];
+    for (interrupt, handler) in interrupts.iter().zip(handlers.iter()) {
+        vectors[interrupt.value as usize] = quote!(Vector { handler: #handler });
+    }
+
+    vec![
+        quote! {
+            #[allow(dead_code, non_snake_case)]
+            extern "C" {
+                #(fn #handlers();)*
+            }
+
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            pub union Vector {
+                handler: unsafe extern "C" fn(),
+                reserved: usize,
+            }
+
+            #[doc(hidden)]
+            #[link_section = ".vector_table.interrupts"]
+            #[no_mangle]
+            pub static __INTERRUPTS: [Vector; #len] = [
+                #(#vectors),*
+            ];
+        }
+    ]
+}
+
+/// Generate the memory mapped hardware definition for the device as formatted Rust source.
+pub fn gen_source(device: &Device, options: &Options) -> String {
+    let items = gen_device(device, options);
+    let file = syn::parse2::<syn::File>(quote!(#(#items)*))
+        .expect("generated software is not valid Rust");
+    prettyplease::unparse(&file)
+}
+
+/// Write the GNU ld linker script defining the symbols the `extern` statics of the device's
+/// peripherals link against.
+pub fn gen_link_mem<W: io::Write>(device: &Device, options: &Options, out: &mut W)
+                                  -> io::Result<()> {
+    for line in link_mem_lines(device, options) {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Name of the linker symbol of a peripheral of the device.
+fn link_symbol(device: &Device, periph_name: &str) -> String {
+    (LINK_MEM_PREFIX.to_owned() + &device.name + "_" + periph_name).to_snake_case()
+}
+
+/// Generate the linker symbol assignment for each of the device's peripherals. With the vector
+/// table each interrupt handler is provided by `DefaultHandler` unless otherwise defined.
+///
+/// GNU ld requires each assignment be terminated by a semicolon.
+fn link_mem_lines(device: &Device, options: &Options) -> Vec<String> {
+    // Peripherals at constant addresses don't link against symbols.
+    let peripherals = if options.const_addresses { &[][..] } else { &device.peripherals[..] };
+    let mut lines: Vec<String> = peripherals.iter()
+        .map(|periph| {
+            let assignment = format!("{} = 0x{:08x}", link_symbol(device, &periph.name),
+                                     periph.base_address);
+            if options.provide_link_symbols {
+                format!("PROVIDE({});", assignment)
+            } else {
+                assignment + ";"
+            }
+        })
+        .collect();
+
+    if options.vector_table {
+        for interrupt in interrupts(device) {
+            lines.push(format!("PROVIDE({} = DefaultHandler);", interrupt.name));
+        }
+    }
+
+    lines
+}
+
+/// A register or cluster placed in a peripheral or cluster.
+enum Member<'a> {
+    Register(Register),
+    Cluster(&'a Cluster),
+}
+
+/// A member as laid out in its parent once any `dim` array has been expanded.
+struct Slot<'a> {
+    member: Member<'a>,
+    /// Name of the struct member.
+    name: String,
+    address_offset: u32,
+    /// Number of elements when the member is laid out as an array.
+    len: Option<u32>,
+}
+
+impl<'a> Slot<'a> {
+    /// Type of each element.
+    fn ty(&self) -> Ident {
+        match self.member {
+            Member::Register(ref reg) => id(&reg.name.to_pascal_case()),
+            Member::Cluster(cluster) => cluster_ty_name(cluster),
+        }
+    }
+
+    /// Number of bytes occupied by each element.
+    fn element_size(&self) -> u32 {
+        match self.member {
+            Member::Register(ref reg) => reg.byte_size(),
+            Member::Cluster(cluster) => cluster_size(cluster),
+        }
+    }
+
+    /// Alignment in bytes of each element.
+    fn align(&self) -> u32 {
+        match self.member {
+            Member::Register(ref reg) => reg.byte_size(),
+            Member::Cluster(cluster) => block_extent(&block_slots(&cluster.registers,
+                                                                  &cluster.clusters)).1,
         }
     }
 
-    // Set of module names already defined.
-    let mut module_name_set: BTreeSet<&str> = BTreeSet::new();
+    /// Number of bytes occupied by the member or array of members.
+    fn byte_size(&self) -> u32 {
+        self.element_size() * self.len.unwrap_or(1)
+    }
 
-    let mut peripheral_items = Vec::new();
-    for periph in device.peripherals.iter() {
+    /// Generate the type of the struct member.
+    fn gen_ty(&self) -> TokenStream {
+        let ty = self.ty();
+        match self.len {
+            Some(len) => {
+                let len = len as usize;
+                quote!([#ty; #len])
+            }
+            None => quote!(#ty),
+        }
+    }
+}
 
-        if periph.derived_from.is_none() {
-            let periph_items = gen_periph(cx, periph);
+/// Expand a `dim` element into an array when its name is of the form `AFR[%s]` and its elements
+/// are contiguous. Otherwise an element is produced for each index with `%s` replaced by the
+/// index. Each element is given as its name, address offset and array length.
+///
+/// As in the SVD format, a name with a bare `%s` such as `OUT%s` is a list of distinct elements,
+/// so it is always split into `OUTA`, `OUTB`, ... even when the elements are contiguous.
+fn expand_dim(name: &str, address_offset: u32, dim: Option<&Dim>, size: u32)
+              -> Vec<(String, u32, Option<u32>)> {
+    let dim = match dim {
+        Some(dim) => dim,
+        None => return vec![(name.to_owned(), address_offset, None)],
+    };
 
-            // Wrap the peripheral items in a module.
-            let name = periph.name.as_str();
-            let group_name = periph.group_name.as_ref();
+    if name.contains("[%s]") && dim.dim_increment == size {
+        return vec![(name.replace("[%s]", ""), address_offset, Some(dim.dim))];
+    }
 
-            let periph_name = if derived_from.contains_key(name) &&
-                group_name.is_some() &&
-                !module_name_set.contains(group_name.unwrap().as_str()) {
-                    group_name.unwrap()
-                } else {
-                    name
-                };
-            let periph_mod_name = builder.id(periph_name.to_snake_case());
-            module_name_set.insert(periph_name);
+    dim.indices()
+        .iter()
+        .enumerate()
+        .map(|(i, index)| {
+            (name.replace("[%s]", index).replace("%s", index),
+             address_offset + i as u32 * dim.dim_increment,
+             None)
+        })
+        .collect()
+}
 
-            // Build the variables that represent access to the hardware.
-            let link_name = String::from(LINK_MEM_PREFIX.to_owned() + &device.name + "_" + &periph.name).to_snake_case();
-            let periph_ty = builder.id(
-                periph.group_name.as_ref().unwrap_or(&periph_name.to_owned()).to_pascal_case());
-            let periph_name = builder.id(periph.name.to_constant_case());
-
-            // Build the links to memory mapped registers.
-            let mut statics = Vec::new();
-            let item = quote_item!(&cx,
-                                   #[allow(dead_code)]
-                                   extern {
-                                       #[link_name=$link_name]
-                                       pub static $periph_name: $periph_ty;
-                                   }).unwrap();
-            statics.push(item);
-            if let Some(set) = derived_from.get(&periph.name.borrow()) {
-                for periph_name in set {
-                    let link_name =
-                        String::from(LINK_MEM_PREFIX.to_owned() +
-                                     &device.name + "_" +
-                                     periph_name).to_snake_case();
-                    let periph_name = builder.id(periph_name.to_constant_case());
-                    let item = quote_item!(&cx,
-                                           #[allow(dead_code)]
-                                           extern {
-                                               #[link_name=$link_name]
-                                               pub static $periph_name: $periph_ty;
-                                           }).unwrap();
-                    statics.push(item);
+/// Lay out the registers and clusters of a peripheral or cluster.
+fn block_slots<'a>(registers: &[Register], clusters: &'a [Cluster]) -> Vec<Slot<'a>> {
+    let mut slots = Vec::new();
+
+    for reg in registers {
+        let elements = expand_dim(&reg.name, reg.address_offset, reg.dim.as_ref(),
+                                  reg.byte_size());
+        for (i, (name, address_offset, len)) in elements.into_iter().enumerate() {
+            // Each register outside of an array is given a type of its own, with the enumerations
+            // of its fields defined by the first.
+            let mut register = reg.clone();
+            register.name = name.clone();
+            register.address_offset = address_offset;
+            register.dim = None;
+            if i > 0 {
+                for field in register.fields.iter_mut().flatten() {
+                    for ev in field.enumerated_values.iter_mut() {
+                        if ev.base_field.is_none() {
+                            ev.base_field = Some(field.name.clone());
+                        }
+                    }
                 }
             }
+            slots.push(Slot { member: Member::Register(register), name, address_offset, len });
+        }
+    }
 
-            let periph_item = quote_item!(&cx, pub mod $periph_mod_name {
-                use volatile_cell::VolatileCell;
-                use core::ops::Drop;
-
-                $periph_items
-                $statics
-            }).unwrap();
-
-            //v.append(&mut gen_periph(cx, periph));
-            peripheral_items.push(periph_item);
+    for cluster in clusters {
+        let elements = expand_dim(&cluster.name, cluster.address_offset, cluster.dim.as_ref(),
+                                  cluster_size(cluster));
+        for (name, address_offset, len) in elements {
+            slots.push(Slot { member: Member::Cluster(cluster), name, address_offset, len });
         }
     }
 
-    // Create module housing the hardware.
-    let dev_name =  builder.id(device.name.to_snake_case());
-    let dev_item = quote_item!(&cx, pub mod $dev_name {
-        $peripheral_items
-    }).unwrap();
+    slots
+}
 
-    let mut v = Vec::new();
-    v.push(dev_item);
-    v
+/// The end of the last member and the largest alignment of the members.
+fn block_extent(slots: &[Slot]) -> (u32, u32) {
+    slots.iter().fold((0, 1), |(end, align), slot| {
+        (end.max(slot.address_offset + slot.byte_size()), align.max(slot.align()))
+    })
 }
 
-/// Print to standard output linker information for the device.
-pub fn gen_link_mem(device: &Device) {
-    for periph in device.peripherals.iter() {
-        let name = String::from(LINK_MEM_PREFIX.to_owned() +
-                                &device.name + "_" +
-                                periph.name.as_str()).to_snake_case();
-        println!("{} = 0x{:08x}", name, periph.base_address);
+/// Name of the struct generated for a cluster.
+fn cluster_ty_name(cluster: &Cluster) -> Ident {
+    id(&cluster.name.replace("[%s]", "").replace("%s", "").to_pascal_case())
+}
+
+/// Number of bytes occupied by a cluster. A `dim` cluster is padded out to its increment so that
+/// it may be laid out as an array.
+fn cluster_size(cluster: &Cluster) -> u32 {
+    let (end, align) = block_extent(&block_slots(&cluster.registers, &cluster.clusters));
+    let size = end.div_ceil(align) * align;
+    match cluster.dim {
+        Some(ref dim) => size.max(dim.dim_increment),
+        None => size,
     }
 }
 
 /// Generate definition of a peripheral.
-fn gen_periph(cx: &ExtCtxt, periph: &Peripheral) -> Vec<P<syntax::ast::Item>> {
+fn gen_periph(periph: &Peripheral) -> Vec<TokenStream> {
+    let periph_name = id(
+        &periph.group_name.as_ref().unwrap_or(&periph.name).to_pascal_case());
+
+    gen_block(&periph_name, periph.description.as_deref(),
+              periph.registers.as_deref().unwrap_or(&[]), &periph.clusters, None)
+}
+
+/// Generate definition of a cluster as a struct within a module of its own which houses the
+/// types of its registers.
+fn gen_cluster(cluster: &Cluster) -> Vec<TokenStream> {
+    let cluster_name = cluster_ty_name(cluster);
+    let cluster_mod_name = id(&cluster_name.to_string().to_snake_case());
+    let cluster_items = gen_block(&cluster_name, cluster.description.as_deref(),
+                                  &cluster.registers, &cluster.clusters,
+                                  Some(cluster_size(cluster)));
+
+    vec![
+        quote! {
+            pub mod #cluster_mod_name {
+                use volatile_cell::VolatileCell;
+                use core::ops::Drop;
+
+                #(#cluster_items)*
+            }
+
+            pub use self::#cluster_mod_name::#cluster_name;
+        }
+    ]
+}
+
+/// Generate the struct laying out registers and clusters at their address offsets along with the
+/// types of its members. The struct is padded out to `size` bytes when given.
+fn gen_block(block_name: &Ident, description: Option<&str>, registers: &[Register],
+             clusters: &[Cluster], size: Option<u32>) -> Vec<TokenStream> {
     let mut v = Vec::new();
-    let builder = aster::AstBuilder::new();
-
-    let periph_name = builder.id(
-        periph.group_name.as_ref().unwrap_or(&periph.name).to_pascal_case());
-
-    // Construct the vector of registers.
-    let mut reg_vec = Vec::new();
-    if let Some(regs) = periph.registers.as_ref() {
-        // Sort the registers by their address offset before adding them to the struct represented
-        // in C style.
-        let mut sorted_regs: Vec<&Register> = regs.iter().map(|x| x).collect();
-        sorted_regs.sort_by_key(|r| r.address_offset);
-        let mut offset = 0u32;
-        let mut pad_num = 0;
-        for reg in sorted_regs {
-            if reg.address_offset < offset {
-                // We have registers that are located at same memory address as other registers.
-                // Let's just ignore them.
-                // FIXME should we handle this better?
-                continue;
-
-            } else if offset != reg.address_offset {
-                // We need to introduce padding into the struct.
-                let pad_name = builder.id(format!("_pad{}", pad_num));
+    let block_doc = doc(description);
+
+    let slots = block_slots(registers, clusters);
+
+    // Construct the vector of members, accessors for those overlapping another, and checks that
+    // each lands at its address offset.
+    let mut member_vec = Vec::new();
+    let mut alt_vec = Vec::new();
+    let mut layout_checks = Vec::new();
+    let mut offset = 0u32;
+
+    // Sort the members by their address offset before adding them to the struct represented in
+    // C style.
+    let mut sorted_slots: Vec<&Slot> = slots.iter().collect();
+    sorted_slots.sort_by_key(|s| s.address_offset);
+    let mut pad_num = 0;
+    for slot in sorted_slots {
+        let var_name = id(&slot.name.to_snake_case());
+        let ty = slot.gen_ty();
+        let address_offset = slot.address_offset as usize;
+
+        // A member at an offset its type can't be aligned to could neither be laid out by the
+        // struct nor referenced through an accessor.
+        if slot.address_offset % slot.align() != 0 {
+            let kind = match slot.member {
+                Member::Register(_) => "register",
+                Member::Cluster(_) => "cluster",
+            };
+            panic!("{} {} at address offset {:#x} isn't aligned to {} bytes",
+                   kind, slot.name, slot.address_offset, slot.align());
+        }
+
+        if slot.address_offset < offset {
+            // The member overlaps one already in the struct, for example the input and output
+            // modes of a timer's capture/compare register. It is reached through an accessor
+            // viewing the same memory as its own type.
+            alt_vec.push(quote! {
+                pub fn #var_name(&self) -> &#ty {
+                    unsafe {
+                        &*((self as *const Self as *const u8).add(#address_offset)
+                            as *const #ty)
+                    }
+                }
+            });
+            layout_checks.push(quote! {
+                assert!(#address_offset + ::core::mem::size_of::<#ty>() <=
+                        ::core::mem::size_of::<#block_name>());
+            });
+
+            // Pad out the struct should the alternate view extend beyond the members already in
+            // it.
+            let end = slot.address_offset + slot.byte_size();
+            if end > offset {
+                let pad_name = id(&format!("_pad{}", pad_num));
                 pad_num += 1;
 
-                let delta = (reg.address_offset - offset) as usize;
-                let tts = quote_tokens!(&cx, $pad_name: [u8; $delta],);
-                reg_vec.push(tts);
+                let delta = (end - offset) as usize;
+                member_vec.push(quote!(#pad_name: [u8; #delta],));
+                offset = end;
             }
+            continue;
 
-            let reg_var_name = builder.id(reg.name.to_snake_case());
-            let reg_ty_name = builder.id(reg.name.to_pascal_case());
-            let tts = quote_tokens!(&cx, pub $reg_var_name: $reg_ty_name,);
-            reg_vec.push(tts);
+        } else if offset != slot.address_offset {
+            // We need to introduce padding into the struct.
+            let pad_name = id(&format!("_pad{}", pad_num));
+            pad_num += 1;
+
+            let delta = (slot.address_offset - offset) as usize;
+            member_vec.push(quote!(#pad_name: [u8; #delta],));
+        }
+
+        member_vec.push(quote!(pub #var_name: #ty,));
+
+        layout_checks.push(quote! {
+            assert!(::core::mem::offset_of!(#block_name, #var_name) == #address_offset);
+        });
+
+        offset = slot.address_offset + slot.byte_size();
+    }
+
+    // Fail the build rather than access the wrong address should the layout of the struct not
+    // match the SVD. Without an explicit size the struct is padded out to its alignment following
+    // the last member.
+    let size_check = match size {
+        Some(size) => {
+            if size > offset {
+                let pad_name = id(&format!("_pad{}", pad_num));
+                let delta = (size - offset) as usize;
+                member_vec.push(quote!(#pad_name: [u8; #delta],));
+            }
 
-            offset = reg.address_offset + 4;
+            let size = size as usize;
+            quote!(assert!(::core::mem::size_of::<#block_name>() == #size);)
         }
+        None => {
+            let end = offset as usize;
+            quote! {
+                let align = ::core::mem::align_of::<#block_name>();
+                assert!(::core::mem::size_of::<#block_name>() == #end.div_ceil(align) * align);
+            }
+        }
+    };
+
+    v.push(
+        quote! {
+            #block_doc
+            #[allow(dead_code, missing_docs)]
+            #[repr(C)]
+            pub struct #block_name {
+                #(#member_vec)*
+            }
+        });
+
+    if !alt_vec.is_empty() {
+        v.push(
+            quote! {
+                #[allow(dead_code, missing_docs)]
+                impl #block_name {
+                    #(#alt_vec)*
+                }
+            });
     }
 
-    let item = quote_item!(&cx,
-                           #[allow(dead_code, missing_docs)]
-                           #[repr(C)]
-                           pub struct $periph_name
-                           {
-                               $reg_vec
-                           }).unwrap();
-    v.push(item);
+    v.push(
+        quote! {
+            const _: () = {
+                #size_check
+                #(#layout_checks)*
+            };
+        });
 
-    if let Some(regs) = periph.registers.as_ref() {
-        for reg in regs {
-            v.append(&mut reg.gen_mmap(cx));
+    for slot in &slots {
+        if let Member::Register(ref reg) = slot.member {
+            v.append(&mut reg.gen_mmap());
         }
     }
+    for cluster in clusters {
+        v.append(&mut gen_cluster(cluster));
+    }
 
     v
 }
 
-#[plugin_registrar]
-pub fn plugin_registrar(reg: &mut Registry) {
-    reg.register_macro("svd_mmap", macro_svd_mmap);
-}
+#[cfg(test)]
+mod tests {
 
-pub struct MacItems {
-    items: Vec<P<ast::Item>>,
-}
+    use proc_macro2::{Delimiter, TokenStream, TokenTree};
+    use std::fs::File;
+    use std::io::prelude::*;
+    use svd::{Access, BitRange, Device, EnumeratedValue, EnumeratedValues, Field, Peripheral,
+              Register, Usage};
+    use super::{GenField, GenReg, Options};
+
+    /// SVD file used for exercising whole device generation.
+    const TEST_SVD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/STM32L4x6.svd");
+
+    /// Tokenize Rust source so that it can be compared against generated software regardless of
+    /// formatting.
+    fn tokens<T: ToString>(s: T) -> String {
+        fn flatten(ts: TokenStream, out: &mut Vec<String>) {
+            for tt in ts {
+                match tt {
+                    TokenTree::Group(g) => {
+                        let (open, close) = match g.delimiter() {
+                            Delimiter::Parenthesis => ("(", ")"),
+                            Delimiter::Brace => ("{", "}"),
+                            Delimiter::Bracket => ("[", "]"),
+                            Delimiter::None => ("", ""),
+                        };
+                        out.push(open.to_owned());
+                        flatten(g.stream(), out);
+                        out.push(close.to_owned());
+                    }
+                    tt => out.push(tt.to_string()),
+                }
+            }
+        }
 
-impl MacItems {
-    pub fn new(items: Vec<P<ast::Item>>) -> Box<MacResult + 'static> {
-        Box::new(MacItems { items: items })
+        let mut out = Vec::new();
+        flatten(s.to_string().parse().unwrap(), &mut out);
+        out.join(" ")
     }
-}
 
-impl MacResult for MacItems {
-    fn make_items(self: Box<MacItems>) -> Option<SmallVector<P<ast::Item>>> {
-        Some(SmallVector::many(self.items.clone()))
-    }
-}
+    #[test]
+    fn test_svd_gen_to_stdout() {
+        let mut svd_file = File::open(TEST_SVD).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-pub fn macro_svd_mmap(cx: &mut ExtCtxt,
-                      sp: Span,
-                      tts: &[tokenstream::TokenTree])
-                      -> Box<MacResult + 'static> {
-    let mut v = std::vec::Vec::new();
+        // Generate SVD device data from SVD XML.
+        let dev = Device::parse(&s);
 
-    if tts.len() != 1 {
-        cx.span_err(sp, &format!("argument must be single filename, but got {}",
-                                 tts.len()));
-        return DummyResult::any(sp);
+        let items = super::gen_device(&dev, &Options::default());
+        for item in items {
+            println!("{}", item);
+        }
     }
 
-    let filename = match tts[0] {
-        tokenstream::TokenTree::Token(_, token::Literal(token::Lit::Str_(s), _)) => s.to_string(),
-        _ => {
-            cx.span_err(sp, "argument must be filename, but got {}",);
-            return DummyResult::any(sp);
+    #[test]
+    fn test_gen_source() {
+        let mut svd_file = File::open(TEST_SVD).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // Generate SVD device data from SVD XML.
+        let dev = Device::parse(&s);
+
+        let source = super::gen_source(&dev, &Options::default());
+        assert!(source.starts_with("pub mod stm32l4x6 {\n    pub mod spi1 {\n"));
+        assert!(source.contains(r"
+        impl Cr1Get {
+            /// SPI enable
+            #[inline(always)]
+            pub fn spe(&self) -> bool {
+                (self.value >> 6u32) & 1u32 != 0
+            }
         }
-    };
+"));
+    }
 
-    let path = Path::new(&filename);
-    let path_buf =
-        if !path.is_absolute() {
-            let call_site = cx.codemap().source_callsite(sp);
-            let mut cu = PathBuf::from(&cx.codemap().span_to_filename(call_site));
-            cu.pop();
-            cu.push(path);
-            cu
-        } else {
-            path.to_path_buf()
-        };
+    #[test]
+    fn test_gen_derived_periph() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/DERIVED.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-    let mut svd_file = File::open(path_buf).unwrap();
-    let mut s = String::new();
-    svd_file.read_to_string(&mut s).unwrap();
+        let dev = Device::parse(&s);
+        let source = super::gen_source(&dev, &Options::default());
+
+        // Peripherals with the registers of their base share its type, including those derived
+        // through another derived peripheral.
+        assert!(source.contains("pub static USART2: Usart;"));
+        assert!(source.contains("pub static USART3: Usart;"));
+        assert!(!source.contains("pub mod usart2"));
+        assert!(!source.contains("pub mod usart3"));
+
+        // Overriding registers gives the peripheral a module of its own.
+        let uart4 = &source[source.find("pub mod uart4").unwrap()..];
+        assert!(uart4.contains("pub static UART4: Usart;"));
+        assert!(uart4.contains("pub fn over8(&self)"));
+        assert!(uart4.contains("pub fn txe(&self)"));
+        assert!(uart4.contains("pub gtpr: Gtpr,"));
+        assert!(!uart4.contains("pub fn m(&self)"));
+    }
 
-    // Generate SVD device data from SVD XML.
-    let dev = Device::parse(&s);
+    #[test]
+    fn test_gen_derived_enumerated_values() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/DERIVED.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-    v.append(&mut gen_device(cx, &dev));
+        let dev = Device::parse(&s);
+        let tim = dev.peripherals.iter().find(|p| p.name == "TIM").unwrap();
+        let regs = tim.registers.as_ref().unwrap();
+
+        // The register derived from another takes its fields, whose enumerations are reused.
+        let ccmr2 = regs.iter().find(|r| r.name == "CCMR2").unwrap();
+        assert_eq!(ccmr2.address_offset, 0x1c);
+        let oc1m = &ccmr2.fields.as_ref().unwrap()[0];
+        assert_eq!(oc1m.bit_range.offset, 4);
+        assert!(oc1m.gen_type_def(ccmr2).is_none());
+        assert_eq!(oc1m.gen_type_ident(Usage::Read).unwrap().to_string(), "Oc1mOcm");
+
+        // Fields derived from another override its bit offset.
+        let ccer = regs.iter().find(|r| r.name == "CCER").unwrap();
+        let fields = ccer.fields.as_ref().unwrap();
+        assert_eq!(fields.iter().map(|f| f.bit_range.offset).collect::<Vec<_>>(),
+                   vec![1, 5, 9, 12, 13]);
+        assert_eq!(fields[2].gen_type_ident(Usage::Read).unwrap().to_string(), "Cc1pPolarity");
+        assert_eq!(fields[2].enumerated_values[0].values.len(), 2);
+
+        // Enumerations of the same name in different fields are found by their qualified path.
+        let cr = regs.iter().find(|r| r.name == "CR").unwrap();
+        let fields = cr.fields.as_ref().unwrap();
+        assert_eq!(fields[2].gen_type_ident(Usage::Read).unwrap().to_string(), "BState");
+        assert_eq!(fields[2].enumerated_values[0].values.len(), 3);
+        assert_eq!(fields[3].gen_type_ident(Usage::Read).unwrap().to_string(), "AState");
+
+        let source = super::gen_source(&dev, &Options::default());
+        assert_eq!(source.matches("pub enum Oc1mOcm").count(), 1);
+        assert_eq!(source.matches("pub enum Cc1pPolarity").count(), 1);
+    }
 
-    // TODO generate source code from SVD file given.
-    MacItems::new(v)
-}
+    #[test]
+    #[should_panic(expected = "ambiguous enumerated values: STATE")]
+    fn test_ambiguous_enumerated_values() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/DERIVED.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-#[cfg(test)]
-mod tests {
+        // Both fields of the register have enumerations named STATE.
+        Device::parse(&s.replace("CR.B.STATE", "STATE"));
+    }
 
-    use aster::AstBuilder;
-    use aster::name::ToName;
-    use std::fs::File;
-    use std::io::prelude::*;
-    use svd::{Access, BitRange, Device, EnumeratedValue, EnumeratedValues, Field, Peripheral, Register};
-    use syntax::codemap;
-    use syntax::ext::base::{DummyResolver, ExtCtxt};
-    use syntax::ext::expand;
-    use syntax::parse;
-    use syntax::print::pprust::item_to_string;
-    use super::{GenField, GenReg};
-
-    fn make_ext_ctxt<'a>(sess: &'a parse::ParseSess,
-                         macro_loader: &'a mut DummyResolver) -> ExtCtxt<'a> {
-        let info = codemap::ExpnInfo {
-            call_site: codemap::DUMMY_SP,
-            callee: codemap::NameAndSpan {
-                format: codemap::MacroAttribute("test".to_name()),
-                allow_internal_unstable: false,
-                span: None
-            }
-        };
+    #[test]
+    fn test_gen_interrupts() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/INTERRUPTS.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-        let cfg = Vec::new();
-        let ecfg = expand::ExpansionConfig::default(String::new());
+        // The interrupt shared by two peripherals is only taken once.
+        let dev = Device::parse(&s);
+        let names = super::interrupts(&dev).iter()
+            .map(|i| (i.value, i.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![(0, "WWDG"), (6, "EXTI0"), (7, "EXTI1"), (24, "TIM1_BRK_TIM15"),
+                               (37, "USART1"), (38, "USART2")]);
+    }
 
-        let mut cx = ExtCtxt::new(&sess, cfg, ecfg, macro_loader);
-        cx.bt_push(info);
+    #[test]
+    #[should_panic(expected = "interrupts EXTI0 and EXTI1 share the number 6")]
+    fn test_gen_interrupts_shared_number() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/INTERRUPTS.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-        cx
+        let dev = Device::parse(&s.replace("<value>7</value>", "<value>6</value>"));
+        super::interrupts(&dev);
     }
 
     #[test]
-    fn test_svd_gen_to_stdout() {
-        let svd_filename = "/tmp/STM32L4x6.svd";
+    #[should_panic(expected = "interrupt TIM1_BRK_TIM15 has both the numbers 24 and 25")]
+    fn test_gen_interrupts_renumbered() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/INTERRUPTS.svd");
         let mut svd_file = File::open(svd_filename).unwrap();
         let mut s = String::new();
         svd_file.read_to_string(&mut s).unwrap();
 
-        // Generate SVD device data from SVD XML.
+        // Only the second of the peripherals sharing the interrupt gives it another number.
+        let i = s.rfind("<value>24</value>").unwrap();
+        s.replace_range(i..i + "<value>24</value>".len(), "<value>25</value>");
         let dev = Device::parse(&s);
+        super::interrupts(&dev);
+    }
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let mut cx = make_ext_ctxt(&sess, &mut macro_loader);
+    #[test]
+    #[should_panic(expected = "interrupt USART1 number 65536 doesn't fit in 16 bits")]
+    fn test_gen_interrupts_too_large() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/INTERRUPTS.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
 
-        let items = super::gen_device(&mut cx, &dev);
-        for item in items {
-            println!("{}", item_to_string(&item));
+        let dev = Device::parse(&s.replace("<value>37</value>", "<value>65536</value>"));
+        super::gen_source(&dev, &Options::default());
+    }
+
+    #[test]
+    fn test_gen_read_action() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/READ_ACTION.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // Neither register with a read action can be read implicitly.
+        let dev = Device::parse(&s);
+        let periph = &dev.peripherals[0];
+        let registers = periph.registers.as_ref().unwrap();
+        for reg in &registers[..2] {
+            assert!(reg.read_side_effects());
+            let items = reg.gen_mmap();
+            let src = tokens(quote!(#(#items)*));
+            assert!(src.contains(&tokens("pub fn read_with_side_effects(&self)")));
+            assert!(!src.contains(&tokens("pub fn get(&self)")));
+            assert!(!src.contains(&tokens("pub fn read(&self)")));
+            assert!(!src.contains(&tokens("pub fn modify")));
         }
+        assert!(!registers[2].read_side_effects());
+        let items = registers[2].gen_mmap();
+        assert!(tokens(quote!(#(#items)*)).contains(&tokens("pub fn get(&self)")));
+    }
+
+    #[test]
+    fn test_doc() {
+        assert_eq!(tokens(super::doc(Some("Control\n          register   1"))),
+                   tokens(r#"#[doc = " Control register 1"]"#));
+        assert_eq!(tokens(super::doc(Some("DR[7:0] <read> `x` *a_b* \\"))),
+                   tokens(r#"#[doc = " DR\\[7:0\\] \\<read\\> \\`x\\` \\*a\\_b\\* \\\\"]"#));
+        assert!(super::doc(Some(" \n ")).is_empty());
+        assert!(super::doc(None).is_empty());
     }
 
     #[test]
     fn test_gen_link_mem() {
-        let svd_filename = "/tmp/STM32L4x6.svd";
-        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut svd_file = File::open(TEST_SVD).unwrap();
         let mut s = String::new();
         svd_file.read_to_string(&mut s).unwrap();
 
         // Generate SVD device data from SVD XML.
         let dev = Device::parse(&s);
-        super::gen_link_mem(&dev);
+        let mut out = Vec::new();
+        super::gen_link_mem(&dev, &Options::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "mmap_stm32l4x6_spi1 = 0x40013000;\n");
+
+        let options = Options { provide_link_symbols: true, ..Options::default() };
+        let mut out = Vec::new();
+        super::gen_link_mem(&dev, &options, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "PROVIDE(mmap_stm32l4x6_spi1 = 0x40013000);\n");
+    }
+
+    #[test]
+    fn test_gen_const_addresses() {
+        let mut svd_file = File::open(TEST_SVD).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // Peripherals at constant addresses have no linker symbols.
+        let dev = Device::parse(&s);
+        let options = Options { const_addresses: true, ..Options::default() };
+        let src = super::gen_source(&dev, &options);
+        assert!(src.contains("pub const PTR: *const Spi1 = 0x4001_3000 as *const _;"));
+        assert!(!src.contains("link_name"));
+
+        let mut out = Vec::new();
+        super::gen_link_mem(&dev, &options, &mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_register_size_defaults() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/SIZES.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // Registers without a size take it from their peripheral and then their device.
+        let dev = Device::parse(&s);
+        let sizes = dev.peripherals.iter()
+            .map(|x| x.registers.as_ref().unwrap()[0].size)
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, vec![Some(8), Some(16), Some(32), Some(64), Some(8), Some(64)]);
+
+        let byte = &dev.peripherals[0].registers.as_ref().unwrap()[0];
+        assert_eq!(byte.value_ty().to_string(), "u8");
+        assert_eq!(byte.value_lit(0x1ff).to_string(), "255u8");
+
+        // Reset values are kept at the full width of 64-bit registers.
+        let double = &dev.peripherals[3].registers.as_ref().unwrap()[0];
+        assert_eq!(double.reset_value, Some(0x1_0000_0000));
+    }
+
+    #[test]
+    #[should_panic(expected = "writeConstraint range of field DLY is outside of its 4 bits")]
+    fn test_gen_range_outside_field() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/CONSTRAINTS.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // No value of the field is within the range.
+        let dev = Device::parse(&s.replace("<minimum>2</minimum>", "<minimum>20</minimum>"));
+        super::gen_source(&dev, &Options::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "register E at address offset 0xc isn't aligned to 8 bytes")]
+    fn test_gen_unaligned_register() {
+        let svd_filename = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/SIZES.svd");
+        let mut svd_file = File::open(svd_filename).unwrap();
+        let mut s = String::new();
+        svd_file.read_to_string(&mut s).unwrap();
+
+        // A 64-bit register at an offset of 4 modulo 8 can't be laid out.
+        let s = s.replace("<addressOffset>0x8</addressOffset>\n          <size>64</size>",
+                          "<addressOffset>0xc</addressOffset>\n          <size>64</size>");
+        let dev = Device::parse(&s);
+        super::gen_source(&dev, &Options::default());
     }
 
     #[test]
@@ -890,7 +2489,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
         let txe = Field {
             name: "TXE".to_owned(),
@@ -900,7 +2502,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
         let cr = Register {
             name: "CR".to_owned(),
@@ -911,6 +2516,9 @@ mod tests {
             reset_mask: None,
             reset_value: None,
             address_offset: 0x00000000,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
         };
 
         let foo = Field {
@@ -921,7 +2529,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
         let bar = Field {
             name: "BAR".to_owned(),
@@ -931,7 +2542,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
         let baz = Register {
             name: "BAZ".to_owned(),
@@ -942,6 +2556,9 @@ mod tests {
             reset_mask: None,
             reset_value: None,
             address_offset: 0x00000004,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
         };
 
         let periph = Peripheral {
@@ -949,18 +2566,15 @@ mod tests {
             group_name: Some("Test".to_owned()),
             description: None,
             base_address: 0xE000E000,
-            interrupt: None,
+            interrupt: Vec::new(),
             registers: Some(vec![cr, baz]),
+            clusters: Vec::new(),
             derived_from: None,
         };
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
-
-        let items = super::gen_periph(&cx, &periph);
+        let items = super::gen_periph(&periph);
         for item in items {
-            println!("{}", item_to_string(&item));
+            println!("{}", item);
         }
     }
 
@@ -974,7 +2588,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
         let txe = Field {
@@ -985,7 +2602,10 @@ mod tests {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
         let freq = Field {
@@ -996,7 +2616,10 @@ mod tests {
                 width: 4,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
         let reg = Register {
@@ -1008,15 +2631,14 @@ mod tests {
             reset_mask: None,
             reset_value: None,
             address_offset: 0x00000000,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
         };
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
-
-        let items = reg.gen_mmap(&cx);
+        let items = reg.gen_mmap();
         for item in items {
-            println!("{}", item_to_string(&item));
+            println!("{}", item);
         }
     }
 
@@ -1026,6 +2648,9 @@ mod tests {
             name: "CR".to_owned(),
             description: "Control register".to_owned(),
             address_offset: 0x00,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
             size: None,
             access: None,
             reset_value: None,
@@ -1039,21 +2664,22 @@ mod tests {
                                  width: 1,
                              },
                              access: Some(Access::ReadWrite),
-                             enumerated_values: None,
+                             modified_write_values: None,
+                             read_action: None,
+                             write_constraint: None,
+                             enumerated_values: Vec::new(),
                          }])
         };
-        let ref field = register.fields.as_ref().unwrap().get(0).unwrap();
-
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
+        let field = register.fields.as_ref().unwrap().first().unwrap();
 
-        let items = field.gen_get(&cx, &register);
-        assert_eq!(item_to_string(&items.get(1).unwrap()),
-r"impl CrGet {
+        let items = field.gen_get(&register);
+        assert_eq!(tokens(&items[1]),
+                   tokens(r"#[allow(dead_code, missing_docs)]
+impl CrGet {
+    /// Receive enabled
     #[inline(always)]
     pub fn rx(&self) -> bool { (self.value >> 11u32) & 1u32 != 0 }
-}");
+}"));
     }
 
     #[test]
@@ -1063,6 +2689,9 @@ r"impl CrGet {
             name: "CR".to_owned(),
             description: "Control register".to_owned(),
             address_offset: 0x00,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
             size: None,
             access: None,
             reset_value: None,
@@ -1076,19 +2705,19 @@ r"impl CrGet {
                                  width: 1,
                              },
                              access: Some(Access::ReadWrite),
-                             enumerated_values: None,
+                             modified_write_values: None,
+                             read_action: None,
+                             write_constraint: None,
+                             enumerated_values: Vec::new(),
                          }])
         };
-        let ref field = register.fields.as_ref().unwrap().get(0).unwrap();
+        let field = register.fields.as_ref().unwrap().first().unwrap();
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
-
-        let items = field.gen_update(&cx, &register);
-        assert_eq!(item_to_string(&items.get(1).unwrap()),
-r"#[allow(dead_code, missing_docs)]
+        let items = field.gen_update(&register);
+        assert_eq!(tokens(&items[1]),
+                   tokens(r"#[allow(dead_code, missing_docs)]
 impl <'a> CrUpdate<'a> {
+    /// Receive enabled
     #[inline(always)]
     pub fn set_rx<'b>(&'b mut self, new_value: bool) -> &'b mut CrUpdate<'a> {
         self.value =
@@ -1097,7 +2726,62 @@ impl <'a> CrUpdate<'a> {
         self.mask |= 1u32 << 11u32;
         self
     }
-}");
+}"));
+    }
+
+    #[test]
+    fn test_field_gen_mask() {
+        let register = Register {
+            name: "CR".to_owned(),
+            description: "Control register".to_owned(),
+            address_offset: 0x00,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
+            size: None,
+            access: None,
+            reset_value: None,
+            reset_mask: None,
+            fields: Some(vec![
+                         Field {
+                             name: "FREQ".to_owned(),
+                             description: Some("Frequency".to_owned()),
+                             bit_range: BitRange {
+                                 offset: 8,
+                                 width: 4,
+                             },
+                             access: Some(Access::ReadWrite),
+                             modified_write_values: None,
+                             read_action: None,
+                             write_constraint: None,
+                             enumerated_values: Vec::new(),
+                         }])
+        };
+        let field = register.fields.as_ref().unwrap().first().unwrap();
+
+        let items = field.gen_get(&register);
+        assert_eq!(tokens(&items[1]),
+                   tokens(r"#[allow(dead_code, missing_docs)]
+impl CrGet {
+    /// Frequency
+    #[inline(always)]
+    pub fn freq(&self) -> u8 { ((self.value >> 8u32) & 15u32) as u8 }
+}"));
+
+        let items = field.gen_update(&register);
+        assert_eq!(tokens(&items[1]),
+                   tokens(r"#[allow(dead_code, missing_docs)]
+impl <'a> CrUpdate<'a> {
+    /// Frequency
+    #[inline(always)]
+    pub fn set_freq<'b>(&'b mut self, new_value: u8) -> &'b mut CrUpdate<'a> {
+        self.value =
+            (self.value & !(15u32 << 8u32)) |
+                ((new_value as u32) & 15u32) << 8u32;
+        self.mask |= 15u32 << 8u32;
+        self
+    }
+}"));
     }
 
     #[test]
@@ -1110,12 +2794,14 @@ impl <'a> CrUpdate<'a> {
                 width: 1,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
-        let builder = AstBuilder::new();
-        let ty = field.gen_type();
-        assert_eq!(ty, builder.ty().bool());
+        let ty = field.gen_type(Usage::Write);
+        assert_eq!(ty.to_string(), tokens("bool"));
     }
 
     #[test]
@@ -1128,12 +2814,14 @@ impl <'a> CrUpdate<'a> {
                 width: 2,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
-        let builder = AstBuilder::new();
-        let ty = field.gen_type();
-        assert_eq!(ty, builder.ty().u8());
+        let ty = field.gen_type(Usage::Write);
+        assert_eq!(ty.to_string(), tokens("u8"));
     }
 
     #[test]
@@ -1146,11 +2834,16 @@ impl <'a> CrUpdate<'a> {
                 width: 3,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: Some(
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: vec![
                 EnumeratedValues {
                     name: Some("PARITY".to_owned()),
                     usage: None,
                     derived_from: None,
+                    base_field: None,
+                    base_usage: None,
                     values: vec![
                         EnumeratedValue {
                             name: "NONE".to_owned(),
@@ -1170,12 +2863,11 @@ impl <'a> CrUpdate<'a> {
                             value: Some(3),
                             is_default: None,
                         },
-                    ]}),
+                    ]}],
         };
 
-        let builder = AstBuilder::new();
-        let ty = field.gen_type();
-        assert_eq!(ty, builder.ty().id("Parity"));
+        let ty = field.gen_type(Usage::Write);
+        assert_eq!(ty.to_string(), tokens("Parity"));
     }
 
     #[test]
@@ -1188,12 +2880,14 @@ impl <'a> CrUpdate<'a> {
                 width: 9,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: None,
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: Vec::new(),
         };
 
-        let builder = AstBuilder::new();
-        let ty = field.gen_type();
-        assert_eq!(ty, builder.ty().u16());
+        let ty = field.gen_type(Usage::Write);
+        assert_eq!(ty.to_string(), tokens("u16"));
     }
 
     #[test]
@@ -1206,11 +2900,16 @@ impl <'a> CrUpdate<'a> {
                 width: 3,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: Some(
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: vec![
                 EnumeratedValues {
                     name: Some("PARITY".to_owned()),
                     usage: None,
                     derived_from: None,
+                    base_field: None,
+                    base_usage: None,
                     values: vec![
                         EnumeratedValue {
                             name: "NONE".to_owned(),
@@ -1230,16 +2929,29 @@ impl <'a> CrUpdate<'a> {
                             value: Some(3),
                             is_default: None,
                         },
-                    ]}),
+                    ]}],
         };
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
-        let item = field.gen_type_def(&cx);
-        assert_eq!(item_to_string(&item.unwrap()),
-r"#[allow(dead_code, missing_docs)]
-enum Parity { None = 0u32, Even = 2u32, Odd = 3u32, }");
+        let register = Register {
+            name: "CR".to_owned(),
+            description: "Control register".to_owned(),
+            address_offset: 0x00,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
+            size: None,
+            access: None,
+            reset_value: None,
+            reset_mask: None,
+            fields: None,
+        };
+        let item = field.gen_type_def(&register);
+        assert_eq!(tokens(item.unwrap()),
+                   tokens(r"/// UART Parity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code, missing_docs)]
+#[repr(u32)]
+pub enum Parity { None = 0u32, Even = 2u32, Odd = 3u32 }"));
     }
 
     #[test]
@@ -1252,11 +2964,16 @@ enum Parity { None = 0u32, Even = 2u32, Odd = 3u32, }");
                 width: 3,
             },
             access: Some(Access::ReadWrite),
-            enumerated_values: Some(
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: vec![
                 EnumeratedValues {
                     name: None,
                     usage: None,
                     derived_from: None,
+                    base_field: None,
+                    base_usage: None,
                     values: vec![
                         EnumeratedValue {
                             name: "NONE".to_owned(),
@@ -1276,16 +2993,73 @@ enum Parity { None = 0u32, Even = 2u32, Odd = 3u32, }");
                             value: Some(3),
                             is_default: None,
                         },
-                    ]}),
+                    ]}],
+        };
+
+        let register = Register {
+            name: "CR".to_owned(),
+            description: "Control register".to_owned(),
+            address_offset: 0x00,
+            dim: None,
+            modified_write_values: None,
+            read_action: None,
+            size: None,
+            access: None,
+            reset_value: None,
+            reset_mask: None,
+            fields: None,
+        };
+        let item = field.gen_type_def(&register);
+        assert_eq!(tokens(item.unwrap()),
+                   tokens(r"/// UART Parity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(dead_code, missing_docs)]
+#[repr(u32)]
+pub enum UartParity { None = 0u32, Even = 2u32, Odd = 3u32 }"));
+
+    }
+
+    #[test]
+    fn test_field_default_value() {
+        let value = |name: &str, value: Option<u64>| EnumeratedValue {
+            name: name.to_owned(),
+            description: None,
+            value,
+            is_default: if value.is_none() { Some(true) } else { None },
+        };
+        let mut field = Field {
+            name: "MODE".to_owned(),
+            description: None,
+            bit_range: BitRange {
+                offset: 0,
+                width: 64,
+            },
+            access: Some(Access::ReadWrite),
+            modified_write_values: None,
+            read_action: None,
+            write_constraint: None,
+            enumerated_values: vec![
+                EnumeratedValues {
+                    name: None,
+                    usage: None,
+                    derived_from: None,
+                    base_field: None,
+                    base_usage: None,
+                    values: vec![value("B", Some(1)), value("LAST", Some(u64::MAX)),
+                                 value("A", Some(0)), value("C", Some(2)),
+                                 value("OTHER", None)],
+                }],
         };
 
-        let sess = parse::ParseSess::new();
-        let mut macro_loader = DummyResolver;
-        let cx = make_ext_ctxt(&sess, &mut macro_loader);
-        let item = field.gen_type_def(&cx);
-        assert_eq!(item_to_string(&item.unwrap()),
-r"#[allow(dead_code, missing_docs)]
-enum UartParity { None = 0u32, Even = 2u32, Odd = 3u32, }");
+        // The smallest value that isn't enumerated is found without counting through the field.
+        let enum_vals = field.enumerated_values[0].clone();
+        let (default, v) = field.default_value(&enum_vals).unwrap();
+        assert_eq!((default.name.as_str(), v), ("OTHER", 3));
 
+        // A field whose values are all enumerated has no value left for the default.
+        field.bit_range.width = 2;
+        let mut enum_vals = enum_vals;
+        enum_vals.values[1].value = Some(3);
+        assert!(field.default_value(&enum_vals).is_none());
     }
 }
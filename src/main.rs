@@ -5,26 +5,16 @@
 //! Command line software for generating Rust software to interface with memory map defined in SVD
 //! file.
 
-#![feature(rustc_private)]
-
-extern crate aster;
 extern crate clap;
-extern crate svd_parser as svd;
-#[allow(plugin_as_library)]
+extern crate inflections;
 extern crate svd_mmap;
-extern crate syntax;
 
-use aster::name::ToName;
-use clap::App;
+use clap::{App, AppSettings, SubCommand};
+use inflections::Inflect;
 use std::fs::File;
 use std::io::prelude::*;
-use svd::Device;
-use svd_mmap::gen_device;
-use syntax::codemap;
-use syntax::ext::base::{DummyResolver, ExtCtxt};
-use syntax::ext::expand;
-use syntax::parse;
-use syntax::print::pprust::item_to_string;
+use svd_mmap::{gen_link_mem, gen_source, Options};
+use svd_mmap::svd::Device;
 
 fn main() {
 
@@ -32,11 +22,27 @@ fn main() {
         .version("0.1")
         .author("Brandon Edens <brandonedens@gmail.com>")
         .about("Generate memory map from SVD")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .args_from_usage(
-            "<INPUT_SVD>    'The SVD file to use as input'"
+            "<INPUT_SVD>       'The SVD file to use as input'
+             --vector-table    'Generate the Cortex-M vector table of the interrupts'
+             --const-addresses 'Reach peripherals at constant addresses instead of link symbols'"
             )
+        .subcommand(SubCommand::with_name("link")
+                    .about("Generate the linker script of the peripherals' symbols")
+                    .args_from_usage(
+                        "<INPUT_SVD>    'The SVD file to use as input'
+                         [OUTPUT_LD]    'The linker script to write, <device>.ld by default'
+                         --provide      'Assign the symbols with PROVIDE'
+                         --vector-table 'Provide DefaultHandler for the interrupt handlers'"
+                        ))
         .get_matches();
 
+    let (matches, link) = match matches.subcommand_matches("link") {
+        Some(link) => (link, true),
+        None => (&matches, false),
+    };
+
     // Read out the SVD file.
     let svd_filename = matches.value_of("INPUT_SVD").unwrap();
     let mut svd_file = File::open(svd_filename).unwrap();
@@ -46,36 +52,22 @@ fn main() {
     // Generate SVD device data from SVD XML.
     let dev = Device::parse(&s);
 
-    // Generate Rust software for interfacing to memory mapped hardware.
-    let sess = parse::ParseSess::new();
-    let mut macro_loader = DummyResolver;
-    let mut cx = make_ext_ctxt(&sess, &mut macro_loader);
-    let items = gen_device(&mut cx, &dev);
-
-    // Print generated Rust to standard output.
-    for item in items {
-        println!("{}", item_to_string(&item));
-    }
-}
-
-/// Context used for generating Rust software.
-fn make_ext_ctxt<'a>(sess: &'a parse::ParseSess,
-                     macro_loader: &'a mut DummyResolver) -> ExtCtxt<'a> {
-    let info = codemap::ExpnInfo {
-        call_site: codemap::DUMMY_SP,
-        callee: codemap::NameAndSpan {
-            format: codemap::MacroAttribute("test".to_name()),
-            allow_internal_unstable: false,
-            span: None
-        }
+    let options = Options {
+        vector_table: matches.is_present("vector-table"),
+        provide_link_symbols: matches.is_present("provide"),
+        const_addresses: matches.is_present("const-addresses"),
     };
 
-    let cfg = Vec::new();
-    let ecfg = expand::ExpansionConfig::default(String::new());
-
-    let mut cx = ExtCtxt::new(&sess, cfg, ecfg, macro_loader);
-    cx.bt_push(info);
-
-    cx
+    if link {
+        // Write the linker script defining the symbols the generated peripherals link against.
+        let ld_filename = matches.value_of("OUTPUT_LD")
+            .map(str::to_owned)
+            .unwrap_or_else(|| dev.name.to_snake_case() + ".ld");
+        let mut ld_file = File::create(ld_filename).unwrap();
+        gen_link_mem(&dev, &options, &mut ld_file).unwrap();
+    } else {
+        // Generate Rust software for interfacing to memory mapped hardware and print it to
+        // standard output.
+        print!("{}", gen_source(&dev, &options));
+    }
 }
-
@@ -0,0 +1,801 @@
+// Copyright 2016 by the svd-mmap project developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Model of the parts of a CMSIS SVD file that are used to generate Rust.
+//!
+//! The types mirror those of Jorge Aparicio's svd-parser crate which this project originally
+//! depended upon. Every release of that crate with this API depends upon a yanked version of
+//! xml-rs so the model is kept here instead.
+
+use std::convert::TryFrom;
+use std::slice;
+use xmltree::{Element, XMLNode};
+
+/// Panic with the location of the failed SVD lookup.
+macro_rules! expect {
+    ($e:expr) => {
+        $e.expect(concat!(file!(), ":", line!(), " ", stringify!($e)))
+    };
+}
+
+trait ElementExt {
+    /// Find the text of the child element with the given name.
+    fn get_child_text(&self, k: &str) -> Option<String>;
+
+    /// Iterate over the element children of this element.
+    fn elements(&self) -> Box<dyn Iterator<Item = &Element> + '_>;
+}
+
+impl ElementExt for Element {
+    fn get_child_text(&self, k: &str) -> Option<String> {
+        self.get_child(k).map(|c| expect!(c.get_text()).trim().to_owned())
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = &Element> + '_> {
+        Box::new(self.children.iter().filter_map(|x| x.as_element()))
+    }
+}
+
+/// Parse an integer in any of the forms allowed by SVD.
+fn parse_u64(tree: &Element) -> Option<u64> {
+    let text = expect!(tree.get_text());
+    let text = text.trim();
+
+    if text.starts_with("0x") || text.starts_with("0X") {
+        u64::from_str_radix(&text["0x".len()..], 16).ok()
+    } else if let Some(bits) = text.strip_prefix('#') {
+        // Handle strings in the binary form of:
+        // #01101x1
+        // along with don't care character x (replaced with 0)
+        u64::from_str_radix(&bits.replace('x', "0"), 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Parse an integer in any of the forms allowed by SVD that fits in 32 bits.
+fn parse_u32(tree: &Element) -> Option<u32> {
+    parse_u64(tree).and_then(|x| u32::try_from(x).ok())
+}
+
+/// Elements whose `derivedFrom` attribute is resolved by merging them over their base element.
+const DERIVABLE: &[&str] = &["cluster", "register", "field"];
+
+/// The forms in which the bit position of a field is given.
+const BIT_RANGE_FORMS: &[&[&str]] = &[&["bitRange"], &["lsb", "msb"], &["bitOffset", "bitWidth"]];
+
+/// Find the element named by the `derivedFrom` path along with the element it was found within.
+/// A bare name is looked up among the siblings of the derived element while a dotted path such
+/// as `PERIPH.REG.FIELD` starts at the device.
+fn find_derived<'a>(path: &str, siblings: &'a Element, device: &'a Element)
+                    -> (&'a Element, &'a Element) {
+    let (mut parent, names) = if path.contains('.') {
+        (device, path.split('.').collect::<Vec<_>>())
+    } else {
+        (siblings, vec![path])
+    };
+
+    let (last, names) = expect!(names.split_last());
+    for name in names {
+        parent = expect!(find_named(parent, name));
+    }
+    (parent, expect!(find_named(parent, last)))
+}
+
+/// Find the element with the given name amongst the children of the element, looking through
+/// the `peripherals`, `registers` and `fields` elements that group them.
+fn find_named<'a>(tree: &'a Element, name: &str) -> Option<&'a Element> {
+    tree.elements()
+        .flat_map(|x| match x.name.as_str() {
+            "peripherals" | "registers" | "fields" => x.elements(),
+            _ => Box::new(Some(x).into_iter()),
+        })
+        .find(|x| x.get_child_text("name").as_deref() == Some(name))
+}
+
+/// Merge the children of a derived element over a copy of its base element, found within the
+/// parent element. Named enumerated values taken from the base are referenced with `derivedFrom`
+/// so that their type is reused.
+fn merge_derived(base: &Element, parent: &Element, derived: &Element) -> Element {
+    let mut merged = base.clone();
+    merged.attributes = derived.attributes.clone();
+    merged.attributes.remove("derivedFrom");
+    let path: Vec<String> = parent.get_child_text("name").into_iter()
+        .chain(base.get_child_text("name"))
+        .collect();
+    reference_enumerated_values(&mut merged, &path);
+
+    // Children given by the derived element replace all those of the same name, such as both the
+    // read and write enumerated values of a field. A bit position given in one form replaces the
+    // others, leaving only the remaining half of its own form such as the width of a field whose
+    // offset alone is given.
+    let mut names: Vec<&str> = derived.elements().map(|x| x.name.as_str()).collect();
+    for form in BIT_RANGE_FORMS {
+        if !form.iter().any(|x| names.contains(x)) {
+            continue;
+        }
+        for other in BIT_RANGE_FORMS.iter().filter(|x| *x != form) {
+            names.extend_from_slice(other);
+        }
+    }
+    merged.children.retain(|x| match *x {
+        XMLNode::Element(ref x) => !names.contains(&x.name.as_str()),
+        _ => true,
+    });
+    merged.children.extend(derived.elements().map(|x| XMLNode::Element(x.clone())));
+
+    merged
+}
+
+/// Replace the named enumerated values within the element with references to them by their path,
+/// which starts with the given path of the element.
+fn reference_enumerated_values(tree: &mut Element, path: &[String]) {
+    for child in tree.children.iter_mut() {
+        if let XMLNode::Element(ref mut child) = *child {
+            let name = child.get_child_text("name");
+            let mut path = path.to_vec();
+            path.extend(name.clone());
+
+            if child.name != "enumeratedValues" {
+                reference_enumerated_values(child, &path);
+            } else if name.is_some() && !child.attributes.contains_key("derivedFrom") {
+                // The usage is kept to tell apart values read and written under the same name.
+                child.children.retain(|x| match *x {
+                    XMLNode::Element(ref x) => x.name == "usage",
+                    _ => false,
+                });
+                child.attributes.insert("derivedFrom".to_owned(), path.join("."));
+            }
+        }
+    }
+}
+
+/// Resolve the `derivedFrom` attribute of an element against the siblings it is found among.
+fn resolve_derived(tree: &Element, siblings: &Element, device: &Element) -> Element {
+    let derived = match tree.attributes.get("derivedFrom") {
+        Some(path) if DERIVABLE.contains(&tree.name.as_str()) => {
+            let (parent, base) = find_derived(path, siblings, device);
+            merge_derived(&resolve_derived(base, parent, device), parent, tree)
+        }
+        _ => tree.clone(),
+    };
+
+    // Resolve the children of the element now that its own children are known.
+    let mut resolved = derived.clone();
+    for child in resolved.children.iter_mut() {
+        if let XMLNode::Element(ref mut child) = *child {
+            *child = resolve_derived(child, &derived, device);
+        }
+    }
+    resolved
+}
+
+#[derive(Clone, Debug)]
+pub struct Device {
+    pub name: String,
+    pub peripherals: Vec<Peripheral>,
+    pub defaults: Defaults,
+}
+
+impl Device {
+    /// Parse the contents of a SVD file.
+    pub fn parse(svd: &str) -> Device {
+        let tree = &expect!(Element::parse(svd.as_bytes()));
+        let tree = &resolve_derived(tree, tree, tree);
+        let defaults = Defaults::parse(tree);
+
+        let mut peripherals: Vec<Peripheral> = expect!(tree.get_child("peripherals"))
+            .elements()
+            .map(|x| Peripheral::parse(x, &defaults))
+            .collect();
+
+        let named: Vec<_> = peripherals.iter()
+            .flat_map(|p| named_enumerated_values(slice::from_ref(&p.name),
+                                                  p.registers.iter().flatten(),
+                                                  &p.clusters))
+            .collect();
+        for periph in peripherals.iter_mut() {
+            resolve_enumerated_values(slice::from_ref(&periph.name),
+                                      periph.registers.as_mut().map_or(&mut [], |r| &mut r[..]),
+                                      &mut periph.clusters,
+                                      &named);
+        }
+
+        Device {
+            name: expect!(tree.get_child_text("name")),
+            peripherals,
+            defaults,
+        }
+    }
+}
+
+/// The named enumerated values, which aren't themselves derived, of the fields of the registers
+/// and clusters of a block along with their path of the form `REG.FIELD.NAME` prefixed by that of
+/// the block.
+fn named_enumerated_values<'a, I>(prefix: &[String], registers: I, clusters: &[Cluster])
+                                  -> Vec<(Vec<String>, EnumeratedValues)>
+    where I: Iterator<Item = &'a Register>
+{
+    let mut named = Vec::new();
+    for reg in registers {
+        for field in reg.fields.iter().flatten() {
+            for ev in field.enumerated_values.iter() {
+                if let (Some(name), None) = (ev.name.as_ref(), ev.derived_from.as_ref()) {
+                    let mut path = prefix.to_vec();
+                    path.extend_from_slice(&[reg.name.clone(), field.name.clone(), name.clone()]);
+                    named.push((path, ev.clone()));
+                }
+            }
+        }
+    }
+
+    for cluster in clusters {
+        let mut prefix = prefix.to_vec();
+        prefix.push(cluster.name.clone());
+        named.append(&mut named_enumerated_values(&prefix,
+                                                  cluster.registers.iter(),
+                                                  &cluster.clusters));
+    }
+    named
+}
+
+/// Find the only enumerated values whose path ends with the `derivedFrom` path, which may be the
+/// bare name of the values or be qualified by their field, register and the blocks containing
+/// them. Values read and written separately under the same name are told apart by usage.
+fn find_enumerated_values<'a>(path: &str,
+                              usage: Option<Usage>,
+                              named: &'a [(Vec<String>, EnumeratedValues)])
+                              -> Option<&'a (Vec<String>, EnumeratedValues)> {
+    let names: Vec<&str> = path.split('.').collect();
+    let mut found = named.iter().filter(|x| {
+        let other_usage = matches!((usage, x.1.usage),
+                                   (Some(Usage::Read), Some(Usage::Write)) |
+                                   (Some(Usage::Write), Some(Usage::Read)));
+        !other_usage &&
+            x.0.len() >= names.len() && x.0[x.0.len() - names.len()..].iter().eq(names.iter())
+    });
+
+    let base = found.next();
+    if base.is_some() && found.next().is_some() {
+        panic!("ambiguous enumerated values: {}", path);
+    }
+    base
+}
+
+/// Resolve the enumerated values derived from others. The path is first resolved against the
+/// register block of the field, in which case the type of the base field for the same usage is
+/// reused, and then against the device from which the values are copied.
+fn resolve_enumerated_values(prefix: &[String],
+                             registers: &mut [Register],
+                             clusters: &mut [Cluster],
+                             device: &[(Vec<String>, EnumeratedValues)]) {
+    let block = named_enumerated_values(prefix, registers.iter(), &[]);
+
+    let fields = registers.iter_mut().flat_map(|r| r.fields.iter_mut().flatten());
+    for ev in fields.flat_map(|f| f.enumerated_values.iter_mut()) {
+        let path = match ev.derived_from {
+            Some(ref path) => path.clone(),
+            None => continue,
+        };
+
+        let (base_field, base_usage, base) = match find_enumerated_values(&path, ev.usage, &block) {
+            Some((base_path, base)) => {
+                // The base field names the types of its read and write values by their usage
+                // when both have the same name.
+                let paired = block.iter().filter(|x| x.0 == *base_path).count() > 1;
+                (Some(base_path[base_path.len() - 2].clone()),
+                 if paired { base.usage } else { None },
+                 base)
+            }
+            None => {
+                let (_, base) = find_enumerated_values(&path, ev.usage, device)
+                    .unwrap_or_else(|| panic!("unknown enumerated values: {}", path));
+                (None, None, base)
+            }
+        };
+
+        ev.name = base.name.clone();
+        ev.values = base.values.clone();
+        ev.base_field = base_field;
+        ev.base_usage = base_usage;
+    }
+
+    for cluster in clusters.iter_mut() {
+        let mut prefix = prefix.to_vec();
+        prefix.push(cluster.name.clone());
+        resolve_enumerated_values(&prefix, &mut cluster.registers, &mut cluster.clusters, device);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Peripheral {
+    pub name: String,
+    pub group_name: Option<String>,
+    pub description: Option<String>,
+    pub base_address: u32,
+    pub interrupt: Vec<Interrupt>,
+    pub registers: Option<Vec<Register>>,
+    pub clusters: Vec<Cluster>,
+    pub derived_from: Option<String>,
+}
+
+impl Peripheral {
+    /// Parse a peripheral whose registers inherit the given default register properties.
+    fn parse(tree: &Element, defaults: &Defaults) -> Peripheral {
+        assert_eq!(tree.name, "peripheral");
+
+        let defaults = Defaults::parse(tree).inherit(defaults);
+        let (registers, clusters) = match tree.get_child("registers") {
+            Some(rs) => {
+                let (registers, clusters) = parse_registers(rs, &defaults);
+                (Some(registers), clusters)
+            }
+            None => (None, Vec::new()),
+        };
+
+        Peripheral {
+            name: expect!(tree.get_child_text("name")),
+            group_name: tree.get_child_text("groupName"),
+            description: tree.get_child_text("description"),
+            base_address: expect!(parse_u32(expect!(tree.get_child("baseAddress")))),
+            interrupt: tree.elements()
+                .filter(|x| x.name == "interrupt")
+                .map(Interrupt::parse)
+                .collect(),
+            registers,
+            clusters,
+            derived_from: tree.attributes.get("derivedFrom").map(|s| s.to_owned()),
+        }
+    }
+}
+
+/// Parse the registers and clusters that are children of the given element.
+fn parse_registers(tree: &Element, defaults: &Defaults) -> (Vec<Register>, Vec<Cluster>) {
+    let registers = tree.elements()
+        .filter(|x| x.name == "register")
+        .map(|x| Register::parse(x, defaults))
+        .collect();
+    let clusters = tree.elements()
+        .filter(|x| x.name == "cluster")
+        .map(|x| Cluster::parse(x, defaults))
+        .collect();
+    (registers, clusters)
+}
+
+/// A block of registers and nested clusters at an offset within its peripheral or cluster.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    pub name: String,
+    pub description: Option<String>,
+    pub address_offset: u32,
+    pub dim: Option<Dim>,
+    pub registers: Vec<Register>,
+    pub clusters: Vec<Cluster>,
+}
+
+impl Cluster {
+    /// Parse a cluster whose registers inherit the given default register properties.
+    fn parse(tree: &Element, defaults: &Defaults) -> Cluster {
+        assert_eq!(tree.name, "cluster");
+
+        let defaults = Defaults::parse(tree).inherit(defaults);
+        let (registers, clusters) = parse_registers(tree, &defaults);
+
+        Cluster {
+            name: expect!(tree.get_child_text("name")),
+            description: tree.get_child_text("description"),
+            address_offset: expect!(parse_u32(expect!(tree.get_child("addressOffset")))),
+            dim: Dim::parse(tree),
+            registers,
+            clusters,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Interrupt {
+    pub name: String,
+    pub description: Option<String>,
+    pub value: u32,
+}
+
+impl Interrupt {
+    fn parse(tree: &Element) -> Interrupt {
+        Interrupt {
+            name: expect!(tree.get_child_text("name")),
+            description: tree.get_child_text("description"),
+            value: expect!(parse_u32(expect!(tree.get_child("value")))),
+        }
+    }
+}
+
+/// A register whose size, access and reset properties have been resolved against the defaults
+/// of its peripheral and device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub name: String,
+    pub description: String,
+    pub address_offset: u32,
+    pub size: Option<u32>,
+    pub access: Option<Access>,
+    pub reset_value: Option<u64>,
+    pub reset_mask: Option<u64>,
+    pub fields: Option<Vec<Field>>,
+    pub dim: Option<Dim>,
+    pub modified_write_values: Option<ModifiedWriteValues>,
+    pub read_action: Option<ReadAction>,
+}
+
+impl Register {
+    /// Parse a register where any properties it doesn't specify are taken from the defaults.
+    fn parse(tree: &Element, defaults: &Defaults) -> Register {
+        assert_eq!(tree.name, "register");
+
+        let defaults = Defaults::parse(tree).inherit(defaults);
+
+        Register {
+            name: expect!(tree.get_child_text("name")),
+            description: expect!(tree.get_child_text("description")),
+            address_offset: expect!(parse_u32(expect!(tree.get_child("addressOffset")))),
+            size: defaults.size,
+            access: defaults.access,
+            reset_value: defaults.reset_value,
+            reset_mask: defaults.reset_mask,
+            fields: tree.get_child("fields")
+                .map(|fs| fs.elements().map(Field::parse).collect()),
+            dim: Dim::parse(tree),
+            modified_write_values: tree.get_child("modifiedWriteValues")
+                .map(ModifiedWriteValues::parse),
+            read_action: tree.get_child("readAction").map(ReadAction::parse),
+        }
+    }
+}
+
+/// Repetition of an element `dim` times, each `dim_increment` bytes after the last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dim {
+    pub dim: u32,
+    pub dim_increment: u32,
+    pub dim_index: Option<Vec<String>>,
+}
+
+impl Dim {
+    fn parse(tree: &Element) -> Option<Dim> {
+        let dim = expect!(parse_u32(tree.get_child("dim")?));
+
+        Some(Dim {
+            dim,
+            dim_increment: expect!(parse_u32(expect!(tree.get_child("dimIncrement")))),
+            dim_index: tree.get_child_text("dimIndex").map(|t| Dim::parse_index(&t)),
+        })
+    }
+
+    /// Parse a `dimIndex` in the form of a numeric range `0-3`, a letter range `A-D` or a comma
+    /// separated list `A,B,C`.
+    fn parse_index(text: &str) -> Vec<String> {
+        let mut range = text.splitn(2, '-').map(str::trim);
+        if let (Some(start), Some(end)) = (range.next(), range.next()) {
+            if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                return (start..end + 1).map(|i| i.to_string()).collect();
+            }
+            if let (Some(start), Some(end)) = (single_char(start), single_char(end)) {
+                return (start..=end).map(|c| c.to_string()).collect();
+            }
+        }
+
+        text.split(',').map(|x| x.trim().to_owned()).collect()
+    }
+
+    /// The strings substituted for `%s` in the name of each element.
+    pub fn indices(&self) -> Vec<String> {
+        match self.dim_index {
+            Some(ref index) => index.clone(),
+            None => (0..self.dim).map(|i| i.to_string()).collect(),
+        }
+    }
+}
+
+/// The only character of the string, if it is a single character.
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite,
+    ReadWriteOnce,
+    WriteOnce,
+    WriteOnly,
+}
+
+impl Access {
+    fn parse(tree: &Element) -> Access {
+        let text = expect!(tree.get_text());
+
+        match text.trim() {
+            "read-only" => Access::ReadOnly,
+            "read-write" => Access::ReadWrite,
+            "read-writeOnce" => Access::ReadWriteOnce,
+            "write-only" => Access::WriteOnly,
+            "writeOnce" => Access::WriteOnce,
+            _ => panic!("unknown access variant: {}", text),
+        }
+    }
+}
+
+/// The effect of writing to a register or field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModifiedWriteValues {
+    OneToClear,
+    OneToSet,
+    OneToToggle,
+    ZeroToClear,
+    ZeroToSet,
+    ZeroToToggle,
+    Clear,
+    Set,
+    Modify,
+}
+
+impl ModifiedWriteValues {
+    fn parse(tree: &Element) -> ModifiedWriteValues {
+        let text = expect!(tree.get_text());
+
+        match text.trim() {
+            "oneToClear" => ModifiedWriteValues::OneToClear,
+            "oneToSet" => ModifiedWriteValues::OneToSet,
+            "oneToToggle" => ModifiedWriteValues::OneToToggle,
+            "zeroToClear" => ModifiedWriteValues::ZeroToClear,
+            "zeroToSet" => ModifiedWriteValues::ZeroToSet,
+            "zeroToToggle" => ModifiedWriteValues::ZeroToToggle,
+            "clear" => ModifiedWriteValues::Clear,
+            "set" => ModifiedWriteValues::Set,
+            "modify" => ModifiedWriteValues::Modify,
+            _ => panic!("unknown modified write values variant: {}", text),
+        }
+    }
+}
+
+/// The side effect of reading a register or field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadAction {
+    Clear,
+    Set,
+    Modify,
+    ModifyExternal,
+}
+
+impl ReadAction {
+    fn parse(tree: &Element) -> ReadAction {
+        let text = expect!(tree.get_text());
+
+        match text.trim() {
+            "clear" => ReadAction::Clear,
+            "set" => ReadAction::Set,
+            "modify" => ReadAction::Modify,
+            "modifyExternal" => ReadAction::ModifyExternal,
+            _ => panic!("unknown read action variant: {}", text),
+        }
+    }
+}
+
+/// The values which may be written to a field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WriteConstraint {
+    /// Only the value last read may be written.
+    WriteAsRead(bool),
+    /// Only the enumerated values may be written.
+    UseEnumeratedValues(bool),
+    /// Only the values within the inclusive range may be written.
+    Range { minimum: u32, maximum: u32 },
+}
+
+impl WriteConstraint {
+    fn parse(tree: &Element) -> WriteConstraint {
+        assert_eq!(tree.name, "writeConstraint");
+
+        if let Some(text) = tree.get_child_text("writeAsRead") {
+            WriteConstraint::WriteAsRead(expect!(text.parse()))
+        } else if let Some(text) = tree.get_child_text("useEnumeratedValues") {
+            WriteConstraint::UseEnumeratedValues(expect!(text.parse()))
+        } else {
+            let range = expect!(tree.get_child("range"));
+            WriteConstraint::Range {
+                minimum: expect!(parse_u32(expect!(range.get_child("minimum")))),
+                maximum: expect!(parse_u32(expect!(range.get_child("maximum")))),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub description: Option<String>,
+    pub bit_range: BitRange,
+    pub access: Option<Access>,
+    pub modified_write_values: Option<ModifiedWriteValues>,
+    pub read_action: Option<ReadAction>,
+    pub write_constraint: Option<WriteConstraint>,
+    /// The field's enumerations; a single one or separate ones for reading and writing.
+    pub enumerated_values: Vec<EnumeratedValues>,
+}
+
+impl Field {
+    fn parse(tree: &Element) -> Field {
+        assert_eq!(tree.name, "field");
+
+        Field {
+            name: expect!(tree.get_child_text("name")),
+            description: tree.get_child_text("description"),
+            bit_range: BitRange::parse(tree),
+            access: tree.get_child("access").map(Access::parse),
+            modified_write_values: tree.get_child("modifiedWriteValues")
+                .map(ModifiedWriteValues::parse),
+            read_action: tree.get_child("readAction").map(ReadAction::parse),
+            write_constraint: tree.get_child("writeConstraint").map(WriteConstraint::parse),
+            enumerated_values: tree.elements()
+                .filter(|x| x.name == "enumeratedValues")
+                .map(EnumeratedValues::parse)
+                .collect(),
+        }
+    }
+
+    /// The enumeration of the values read from the field.
+    pub fn read_enumerated_values(&self) -> Option<&EnumeratedValues> {
+        self.enumerated_values.iter().find(|x| x.usage != Some(Usage::Write))
+    }
+
+    /// The enumeration of the values written to the field.
+    pub fn write_enumerated_values(&self) -> Option<&EnumeratedValues> {
+        self.enumerated_values.iter().find(|x| x.usage != Some(Usage::Read))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BitRange {
+    pub offset: u32,
+    pub width: u32,
+}
+
+impl BitRange {
+    fn parse(tree: &Element) -> BitRange {
+        let (end, start): (u32, u32) = if let Some(range) = tree.get_child_text("bitRange") {
+            assert!(range.starts_with('['));
+            assert!(range.ends_with(']'));
+
+            let mut parts = range[1..range.len() - 1].split(':');
+
+            (expect!(expect!(parts.next()).parse()), expect!(expect!(parts.next()).parse()))
+        } else if let (Some(lsb), Some(msb)) = (tree.get_child_text("lsb"),
+                                                tree.get_child_text("msb")) {
+            (expect!(msb.parse()), expect!(lsb.parse::<u32>()))
+        } else {
+            return BitRange {
+                offset: expect!(expect!(tree.get_child_text("bitOffset")).parse()),
+                width: expect!(expect!(tree.get_child_text("bitWidth")).parse()),
+            };
+        };
+
+        BitRange {
+            offset: start,
+            width: end - start + 1,
+        }
+    }
+}
+
+/// Register default properties
+#[derive(Clone, Copy, Debug)]
+pub struct Defaults {
+    pub size: Option<u32>,
+    pub reset_value: Option<u64>,
+    pub reset_mask: Option<u64>,
+    pub access: Option<Access>,
+}
+
+impl Defaults {
+    fn parse(tree: &Element) -> Defaults {
+        Defaults {
+            size: tree.get_child("size").map(|t| expect!(parse_u32(t))),
+            reset_value: tree.get_child("resetValue").map(|t| expect!(parse_u64(t))),
+            reset_mask: tree.get_child("resetMask").map(|t| expect!(parse_u64(t))),
+            access: tree.get_child("access").map(Access::parse),
+        }
+    }
+
+    /// Take any properties not specified here from the enclosing element's defaults.
+    fn inherit(self, parent: &Defaults) -> Defaults {
+        Defaults {
+            size: self.size.or(parent.size),
+            reset_value: self.reset_value.or(parent.reset_value),
+            reset_mask: self.reset_mask.or(parent.reset_mask),
+            access: self.access.or(parent.access),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Usage {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Usage {
+    fn parse(tree: &Element) -> Usage {
+        let text = expect!(tree.get_text());
+
+        match text.trim() {
+            "read" => Usage::Read,
+            "write" => Usage::Write,
+            "read-write" => Usage::ReadWrite,
+            _ => panic!("unknown usage variant: {}", text),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumeratedValues {
+    pub name: Option<String>,
+    pub usage: Option<Usage>,
+    pub derived_from: Option<String>,
+    /// Field of the same register block whose type for these values is reused, resolved from
+    /// `derived_from`.
+    pub base_field: Option<String>,
+    /// Usage of the base field's values when it enumerates those read and written separately
+    /// under the same name.
+    pub base_usage: Option<Usage>,
+    pub values: Vec<EnumeratedValue>,
+}
+
+impl EnumeratedValues {
+    fn parse(tree: &Element) -> EnumeratedValues {
+        assert_eq!(tree.name, "enumeratedValues");
+
+        EnumeratedValues {
+            name: tree.get_child_text("name"),
+            usage: tree.get_child("usage").map(Usage::parse),
+            derived_from: tree.attributes.get("derivedFrom").map(|s| s.to_owned()),
+            base_field: None,
+            base_usage: None,
+            values: tree.elements()
+                .filter_map(EnumeratedValue::parse)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumeratedValue {
+    pub name: String,
+    pub description: Option<String>,
+    pub value: Option<u64>,
+    pub is_default: Option<bool>,
+}
+
+impl EnumeratedValue {
+    fn parse(tree: &Element) -> Option<EnumeratedValue> {
+        if tree.name != "enumeratedValue" {
+            return None;
+        }
+
+        Some(EnumeratedValue {
+            name: expect!(tree.get_child_text("name")),
+            description: tree.get_child_text("description"),
+            value: tree.get_child("value").map(|t| expect!(parse_u64(t))),
+            is_default: tree.get_child_text("isDefault").map(|t| expect!(t.parse())),
+        })
+    }
+}